@@ -0,0 +1,54 @@
+use serde::Deserialize;
+use serde_inline_default::serde_inline_default;
+
+pub const HELP_MESSAGE: &str = "split the function into smaller functions";
+
+pub const EXTRACT_HELP_MESSAGE: &str =
+    "extract the largest nested block into its own function to start with";
+
+/// Default maximum statements in a function body
+const DEFAULT_MAX_STATEMENTS: usize = 50;
+
+/// Default maximum lines of code in a function body
+const DEFAULT_MAX_LINES: usize = 100;
+
+/// Default maximum statements in a test function body
+const DEFAULT_MAX_TEST_STATEMENTS: usize = 100;
+
+/// Default maximum lines of code in a test function body
+const DEFAULT_MAX_TEST_LINES: usize = 200;
+
+/// Lint configuration
+#[serde_inline_default]
+#[derive(Deserialize)]
+pub struct Config {
+    /// Maximum allowed statements in a function body, including nested blocks
+    #[serde_inline_default(DEFAULT_MAX_STATEMENTS)]
+    pub max_statements: usize,
+
+    /// Maximum allowed lines of code (excluding blank and comment-only lines) in a function
+    /// body
+    #[serde_inline_default(DEFAULT_MAX_LINES)]
+    pub max_lines: usize,
+
+    /// Maximum allowed statements in a `#[test]` function or a function in a `#[cfg(test)]`
+    /// module
+    #[serde_inline_default(DEFAULT_MAX_TEST_STATEMENTS)]
+    pub max_test_statements: usize,
+
+    /// Maximum allowed lines of code in a `#[test]` function or a function in a
+    /// `#[cfg(test)]` module
+    #[serde_inline_default(DEFAULT_MAX_TEST_LINES)]
+    pub max_test_lines: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_statements: DEFAULT_MAX_STATEMENTS,
+            max_lines: DEFAULT_MAX_LINES,
+            max_test_statements: DEFAULT_MAX_TEST_STATEMENTS,
+            max_test_lines: DEFAULT_MAX_TEST_LINES,
+        }
+    }
+}
@@ -0,0 +1,356 @@
+#![feature(rustc_private)]
+#![warn(unused_extern_crates)]
+
+mod config;
+
+extern crate rustc_ast;
+extern crate rustc_lexer;
+extern crate rustc_lint;
+extern crate rustc_session;
+extern crate rustc_span;
+
+#[cfg(not(feature = "rlib"))]
+dylint_linting::dylint_library!();
+
+const DESCRIPTION: &str = "function is too long";
+
+/// Note added when the test budgets apply.
+const TEST_NOTE: &str =
+    "test functions are checked against `max_test_statements` and `max_test_lines`";
+
+use config::{Config, EXTRACT_HELP_MESSAGE, HELP_MESSAGE};
+use dylint_linting::config_or_default;
+use rustc_ast::{
+    AssocItem, AssocItemKind, Attribute, Block, Fn, Item, ItemKind, MetaItemInner, NodeId, Stmt,
+    StmtKind,
+    visit::{Visitor, walk_block, walk_stmt},
+};
+use rustc_lexer::{FrontmatterAllowed, TokenKind, tokenize};
+use rustc_lint::{EarlyContext, EarlyLintPass, LintContext, LintStore};
+use rustc_session::{Session, declare_lint, impl_lint_pass};
+use rustc_span::{Span, Symbol, sym};
+
+/// Lint for functions with too many statements or lines
+pub struct FunctionLength {
+    config: Config,
+    /// `#[cfg(test)]` items enclosing the current item.
+    test_items: Vec<NodeId>,
+    /// Name of the test whose `#[rustc_test_marker]` const was visited last. The test harness
+    /// emits that const right before the test function it describes.
+    test_marker: Option<Symbol>,
+}
+
+impl Default for FunctionLength {
+    fn default() -> Self {
+        Self {
+            config: config_or_default(env!("CARGO_PKG_NAME")),
+            test_items: vec![],
+            test_marker: None,
+        }
+    }
+}
+
+#[allow(clippy::no_mangle_with_rust_abi)]
+#[cfg_attr(not(feature = "rlib"), unsafe(no_mangle))]
+pub fn register_lints(sess: &Session, lint_store: &mut LintStore) {
+    dylint_linting::init_config(sess);
+    lint_store.register_lints(&[FUNCTION_LENGTH]);
+    lint_store.register_early_pass(|| Box::new(FunctionLength::default()));
+}
+
+declare_lint! {
+    /// ### What it does
+    /// Checks for functions whose body has more than `max_statements` statements (counted
+    /// through nested blocks and closures) or more than `max_lines` lines of code (blank and
+    /// comment-only lines excluded). Tests get their own, larger budgets. The largest nested
+    /// block is pointed out as the best candidate for extraction.
+    ///
+    /// ### Why is this bad?
+    /// Long functions do many things at once and are hard to read, test and change.
+    ///
+    /// ### Example
+    /// ```rust,no_run
+    /// fn process(input: &str) {
+    ///     // 120 lines of parsing, validation and output
+    /// }
+    /// ```
+    ///
+    /// Use instead:
+    /// ```rust,no_run
+    /// # fn parse(input: &str) -> String { input.to_owned() }
+    /// # fn validate(parsed: &str) {}
+    /// # fn write_output(parsed: &str) {}
+    /// fn process(input: &str) {
+    ///     let parsed = parse(input);
+    ///     validate(&parsed);
+    ///     write_output(&parsed);
+    /// }
+    /// ```
+    pub FUNCTION_LENGTH,
+    Warn,
+    DESCRIPTION
+}
+
+impl_lint_pass!(FunctionLength => [FUNCTION_LENGTH]);
+
+/// Statements in a nested block, including the blocks nested in it.
+struct BlockSize {
+    span: Span,
+    statements: usize,
+}
+
+/// Counts the statements in a function body and finds its largest nested block. Nested items
+/// are skipped, as they are checked on their own, and so are blocks expanded from macros.
+#[derive(Default)]
+struct StatementCounter {
+    statements: usize,
+    largest_block: Option<BlockSize>,
+}
+
+impl<'ast> Visitor<'ast> for StatementCounter {
+    fn visit_stmt(&mut self, stmt: &'ast Stmt) {
+        match stmt.kind {
+            StmtKind::Item(_) => return,
+            StmtKind::Empty => {}
+            _ => self.statements += 1,
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        if block.span.from_expansion() {
+            return;
+        }
+
+        let before = self.statements;
+        walk_block(self, block);
+        let statements = self.statements - before;
+        if statements == 0
+            || self
+                .largest_block
+                .as_ref()
+                .is_some_and(|largest| largest.statements >= statements)
+        {
+            return;
+        }
+        self.largest_block = Some(BlockSize {
+            span: block.span,
+            statements,
+        });
+    }
+}
+
+/// Finds the outermost block written in the source, for function bodies that an attribute
+/// macro like `#[tokio::test]` wraps in generated code. Nested items are skipped.
+#[derive(Default)]
+struct SourceBlockFinder<'ast> {
+    block: Option<&'ast Block>,
+}
+
+impl<'ast> Visitor<'ast> for SourceBlockFinder<'ast> {
+    fn visit_item(&mut self, _item: &'ast Item) {}
+
+    fn visit_block(&mut self, block: &'ast Block) {
+        if self.block.is_some() {
+            return;
+        }
+        if !block.span.from_expansion() {
+            self.block = Some(block);
+            return;
+        }
+        walk_block(self, block);
+    }
+}
+
+/// The function body as written in the source: the body itself, or the outermost source block
+/// inside a body generated by a macro.
+fn source_body(body: &Block) -> Option<&Block> {
+    let mut finder = SourceBlockFinder::default();
+    finder.visit_block(body);
+    finder.block
+}
+
+/// Number of lines in `snippet` with code on them, skipping blank lines and lines that only
+/// hold comments. A token spanning several lines, like a multi-line string literal, puts code
+/// on each of them, and comment markers inside literals are not taken for comments.
+fn code_lines(snippet: &str) -> usize {
+    let mut has_code = vec![false; snippet.matches('\n').count() + 1];
+    let mut line = 0;
+    let mut offset = 0;
+    for token in tokenize(snippet, FrontmatterAllowed::No) {
+        let end = offset + token.len as usize;
+        let newlines = snippet
+            .get(offset..end)
+            .unwrap_or_default()
+            .matches('\n')
+            .count();
+        offset = end;
+        if is_code(token.kind) {
+            has_code
+                .iter_mut()
+                .skip(line)
+                .take(newlines + 1)
+                .for_each(|code| *code = true);
+        }
+        line += newlines;
+    }
+    has_code.iter().filter(|&&code| code).count()
+}
+
+/// Returns `true` for tokens other than whitespace and comments, including doc comments.
+fn is_code(kind: TokenKind) -> bool {
+    !matches!(
+        kind,
+        TokenKind::Whitespace | TokenKind::LineComment { .. } | TokenKind::BlockComment { .. }
+    )
+}
+
+/// Lines of code inside the braces of a block.
+fn block_code_lines(cx: &EarlyContext<'_>, span: Span) -> usize {
+    let Ok(snippet) = cx.sess().source_map().span_to_snippet(span) else {
+        return 0;
+    };
+    let inner = snippet
+        .strip_prefix('{')
+        .and_then(|inner| inner.strip_suffix('}'))
+        .unwrap_or(&snippet);
+    code_lines(inner)
+}
+
+/// Returns `true` if the list of `cfg` predicates requires `test`: `test` itself, or an `all(..)`
+/// that contains it. An `any(..)` also holds without `test`.
+fn requires_test(list: &[MetaItemInner]) -> bool {
+    list.iter().any(|item| {
+        item.has_name(sym::test)
+            || item.has_name(sym::all) && item.meta_item_list().is_some_and(requires_test)
+    })
+}
+
+/// Returns `true` if the item is only compiled for tests, e.g. a `#[cfg(test)]` module. After
+/// expansion, `cfg`s that hold are kept as `cfg_trace` attributes.
+fn is_cfg_test(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        (attr.has_name(sym::cfg_trace) || attr.has_name(sym::cfg))
+            && attr
+                .meta_item_list()
+                .is_some_and(|list| requires_test(&list))
+    })
+}
+
+impl FunctionLength {
+    /// Checks the source body of a function, skipping functions whose name is generated by a
+    /// macro.
+    fn check_fn_body(&self, cx: &EarlyContext<'_>, func: &Fn, is_test: bool) {
+        if func.ident.span.from_expansion() {
+            return;
+        }
+        let Some(body) = func.body.as_deref().and_then(source_body) else {
+            return;
+        };
+
+        let test = is_test || !self.test_items.is_empty();
+        let (max_statements, max_lines) = if test {
+            (self.config.max_test_statements, self.config.max_test_lines)
+        } else {
+            (self.config.max_statements, self.config.max_lines)
+        };
+
+        let mut counter = StatementCounter::default();
+        walk_block(&mut counter, body);
+        let statements = counter.statements;
+        let lines = block_code_lines(cx, body.span);
+        if statements <= max_statements && lines <= max_lines {
+            return;
+        }
+
+        let mut overruns = vec![];
+        if statements > max_statements {
+            overruns.push(format!(
+                "{statements} statements, {max_statements} max allowed"
+            ));
+        }
+        if lines > max_lines {
+            overruns.push(format!("{lines} lines, {max_lines} max allowed"));
+        }
+        let overrun = overruns.join("; ");
+
+        cx.span_lint(FUNCTION_LENGTH, func.ident.span, |diag| {
+            diag.primary_message(format!("function length: {overrun}"));
+            match counter.largest_block {
+                Some(block) => {
+                    let block_lines = block_code_lines(cx, block.span);
+                    diag.span_label(
+                        block.span,
+                        format!(
+                            "largest nested block: {} statements, {block_lines} lines",
+                            block.statements
+                        ),
+                    );
+                    diag.help(EXTRACT_HELP_MESSAGE);
+                }
+                None => {
+                    diag.help(HELP_MESSAGE);
+                }
+            }
+            if test {
+                diag.note(TEST_NOTE);
+            }
+        });
+    }
+}
+
+impl EarlyLintPass for FunctionLength {
+    fn check_item(&mut self, cx: &EarlyContext<'_>, item: &Item) {
+        if is_cfg_test(&item.attrs) {
+            self.test_items.push(item.id);
+        }
+
+        match &item.kind {
+            ItemKind::Const(constant)
+                if item
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.has_name(sym::rustc_test_marker)) =>
+            {
+                self.test_marker = Some(constant.ident.name);
+            }
+            ItemKind::Fn(func) => {
+                let is_test = self.test_marker.take() == Some(func.ident.name);
+                self.check_fn_body(cx, func, is_test);
+            }
+            _ => {}
+        }
+    }
+
+    fn check_item_post(&mut self, _cx: &EarlyContext<'_>, item: &Item) {
+        if self.test_items.last() == Some(&item.id) {
+            self.test_items.pop();
+        }
+    }
+
+    fn check_impl_item(&mut self, cx: &EarlyContext<'_>, item: &AssocItem) {
+        if let AssocItemKind::Fn(func) = &item.kind {
+            self.check_fn_body(cx, func, false);
+        }
+    }
+
+    fn check_trait_item(&mut self, cx: &EarlyContext<'_>, item: &AssocItem) {
+        if let AssocItemKind::Fn(func) = &item.kind {
+            self.check_fn_body(cx, func, false);
+        }
+    }
+}
+
+#[test]
+fn ui() {
+    dylint_uitesting::ui::Test::src_base(env!("CARGO_PKG_NAME"), "ui")
+        .dylint_toml(
+            "[function_length]\n\
+             max_statements = 6\n\
+             max_lines = 10\n\
+             max_test_statements = 8\n\
+             max_test_lines = 12",
+        )
+        .rustc_flags(["--test"])
+        .run();
+}
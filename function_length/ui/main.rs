@@ -0,0 +1,221 @@
+#![allow(unused, clippy::let_and_return, clippy::needless_return)]
+
+// Within budget: 6 statements, 6 lines
+fn short(v: i32) -> i32 {
+    let a = v + 1;
+    let b = a * 2;
+    let c = b - 3;
+    let d = c / 4;
+    let e = d % 5;
+    e
+}
+
+// Blank and comment lines are not counted
+fn commented(v: i32) -> i32 {
+    // first step
+    let a = v + 1;
+
+    /* second
+       step */
+    let b = a * 2;
+
+    // third step
+    let c = b - 3;
+    /* fourth */ let d = c / 4;
+
+    d
+}
+
+//~v ERROR: function length: 8 statements, 6 max allowed
+fn too_many_statements(v: i32) -> i32 {
+    let mut total = v;
+    if total > 10 {
+        total -= 1;
+        total *= 2;
+        total += 3;
+    }
+    for i in 0..v {
+        total += i;
+    }
+    total
+}
+
+//~v ERROR: function length: 11 statements, 6 max allowed; 15 lines, 10 max allowed
+fn too_many_statements_and_lines(values: &[i32]) -> i32 {
+    let mut total = 0;
+    for v in values {
+        if *v > 0 {
+            total += v;
+        } else {
+            total -= v;
+        }
+    }
+    let mean = total / values.len() as i32;
+    values.iter().for_each(|v| {
+        let delta = v - mean;
+        let squared = delta * delta;
+        total += squared;
+    });
+    total
+}
+
+// Nested items are checked on their own
+fn outer_with_nested_item(v: i32) -> i32 {
+    //~v ERROR: function length: 7 statements
+    fn nested(v: i32) -> i32 {
+        let a = v + 1;
+        let b = a + 1;
+        let c = b + 1;
+        let d = c + 1;
+        let e = d + 1;
+        let f = e + 1;
+        f
+    }
+    nested(v)
+}
+
+struct Counter;
+
+impl Counter {
+    //~v ERROR: function length: 7 statements
+    fn method(&self, v: i32) -> i32 {
+        let a = v + 1;
+        let b = a + 1;
+        let c = b + 1;
+        let d = c + 1;
+        let e = d + 1;
+        let f = e + 1;
+        f
+    }
+}
+
+// Tests get the larger test budgets
+#[test]
+fn test_within_test_budget() {
+    let a = 1;
+    let b = a + 1;
+    let c = b + 1;
+    let d = c + 1;
+    let e = d + 1;
+    let f = e + 1;
+    let g = f + 1;
+    assert_eq!(g, 7);
+}
+
+#[cfg(test)]
+mod tests {
+    fn helper_within_test_budget() -> i32 {
+        let a = 1;
+        let b = a + 1;
+        let c = b + 1;
+        let d = c + 1;
+        let e = d + 1;
+        let f = e + 1;
+        let g = f + 1;
+        g
+    }
+
+    #[test]
+    //~v ERROR: function length: 9 statements, 8 max allowed
+    fn test_too_long() {
+        let a = 1;
+        let b = a + 1;
+        let c = b + 1;
+        let d = c + 1;
+        let e = d + 1;
+        let f = e + 1;
+        let g = f + 1;
+        let h = g + 1;
+        assert_eq!(h, 8);
+    }
+}
+
+// Also compiled outside of tests, so checked against the regular budget
+#[cfg(any(test, unix))]
+mod not_only_tests {
+    //~v ERROR: function length: 8 statements, 6 max allowed
+    fn helper_beyond_budget() -> i32 {
+        let a = 1;
+        let b = a + 1;
+        let c = b + 1;
+        let d = c + 1;
+        let e = d + 1;
+        let f = e + 1;
+        let g = f + 1;
+        g
+    }
+}
+
+// Only compiled for tests
+#[cfg(all(test, unix))]
+mod unix_tests {
+    fn helper_within_test_budget() -> i32 {
+        let a = 1;
+        let b = a + 1;
+        let c = b + 1;
+        let d = c + 1;
+        let e = d + 1;
+        let f = e + 1;
+        let g = f + 1;
+        g
+    }
+}
+
+fn main() {}
+
+// A body wrapped in generated code, as by `#[tokio::test]`, is checked as written
+macro_rules! wrapped {
+    (fn $name:ident() $body:block) => {
+        fn $name() {
+            let body = async $body;
+            drop(body);
+        }
+    };
+}
+
+wrapped! {
+    //~v ERROR: function length: 7 statements, 6 max allowed
+    fn wrapped_too_long() {
+        let a = 1;
+        let b = a + 1;
+        let c = b + 1;
+        let d = c + 1;
+        let e = d + 1;
+        let f = e + 1;
+        assert_eq!(f, 6);
+    }
+}
+
+// Within budget: nested block comments are skipped as a whole
+fn nested_block_comment(v: i32) -> i32 {
+    /* outer
+    /* inner */
+    still in the outer comment
+    still in the outer comment
+    still in the outer comment
+    still in the outer comment
+    still in the outer comment
+    still in the outer comment
+    still in the outer comment
+    still in the outer comment
+    still in the outer comment
+    */
+    v
+}
+
+// Over budget: a `/*` inside a string literal does not open a comment
+//~v ERROR: function length: 12 lines, 10 max allowed
+fn glob_in_string() -> Vec<&'static str> {
+    let pattern = "logs/*";
+    vec![
+        pattern,
+        "a",
+        "b",
+        "c",
+        "d",
+        "e",
+        "f",
+        "g",
+        "h",
+    ]
+}
@@ -0,0 +1,32 @@
+use serde::Deserialize;
+
+pub const HELP_MESSAGE: &str = "remove the `else` and unindent its body";
+
+pub const LET_HELP_MESSAGE: &str =
+    "move the diverging `if` before the `let` and bind the `else` value directly";
+
+/// Label for the expression that makes the `then` branch diverge.
+pub const DIVERGES_LABEL: &str = "this branch diverges";
+
+/// Default macros that never return
+fn default_diverging_macros() -> Vec<String> {
+    ["panic", "unreachable", "todo", "unimplemented"]
+        .map(String::from)
+        .to_vec()
+}
+
+/// Lint configuration
+#[derive(Deserialize)]
+pub struct Config {
+    /// Names of macros whose invocation never returns, matched against the invoked name
+    #[serde(default = "default_diverging_macros")]
+    pub diverging_macros: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            diverging_macros: default_diverging_macros(),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+#![feature(rustc_private)]
+#![warn(unused_extern_crates)]
+
+mod config;
+mod suggestion;
+
+extern crate rustc_ast;
+extern crate rustc_errors;
+extern crate rustc_lexer;
+extern crate rustc_lint;
+extern crate rustc_session;
+extern crate rustc_span;
+
+#[cfg(not(feature = "rlib"))]
+dylint_linting::dylint_library!();
+
+const DESCRIPTION: &str = "`else` after a branch that always diverges";
+
+use config::{Config, DIVERGES_LABEL, HELP_MESSAGE, LET_HELP_MESSAGE};
+use dylint_linting::config_or_default;
+use rustc_ast::{Block, Expr, ExprKind, Local, LocalKind, Stmt, StmtKind};
+use rustc_errors::Applicability;
+use rustc_lint::{EarlyContext, EarlyLintPass, LintContext, LintStore};
+use rustc_session::{Session, declare_lint, impl_lint_pass};
+use rustc_span::{ExpnKind, MacroKind, Span};
+use suggestion::{else_removal, let_rewrite};
+
+/// Lint for `else` blocks that can be removed because the `if` branch diverges
+pub struct NeedlessElse {
+    config: Config,
+}
+
+impl Default for NeedlessElse {
+    fn default() -> Self {
+        Self {
+            config: config_or_default(env!("CARGO_PKG_NAME")),
+        }
+    }
+}
+
+#[allow(clippy::no_mangle_with_rust_abi)]
+#[cfg_attr(not(feature = "rlib"), unsafe(no_mangle))]
+pub fn register_lints(sess: &Session, lint_store: &mut LintStore) {
+    dylint_linting::init_config(sess);
+    lint_store.register_lints(&[NEEDLESS_ELSE]);
+    lint_store.register_early_pass(|| Box::new(NeedlessElse::default()));
+}
+
+declare_lint! {
+    /// ### What it does
+    /// Checks for `if` statements whose `then` branch always diverges (ends in `return`,
+    /// `break`, `continue`, a diverging macro like `panic!`, or a `match` or `if` whose every
+    /// branch diverges) but still have an `else`, including `let x = if c { return } else { v };`
+    /// and `if let` chains.
+    ///
+    /// ### Why is this bad?
+    /// The `else` adds a level of nesting for the rest of the code without need. Treating the
+    /// diverging branch as a guard clause keeps the main path unindented.
+    ///
+    /// ### Example
+    /// ```rust,no_run
+    /// # fn f(input: Option<&str>) -> usize {
+    /// if let Some(input) = input && !input.is_empty() {
+    ///     return input.len();
+    /// } else {
+    ///     let fallback = 0;
+    ///     fallback
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Use instead:
+    /// ```rust,no_run
+    /// # fn f(input: Option<&str>) -> usize {
+    /// if let Some(input) = input && !input.is_empty() {
+    ///     return input.len();
+    /// }
+    /// let fallback = 0;
+    /// fallback
+    /// # }
+    /// ```
+    pub NEEDLESS_ELSE,
+    Warn,
+    DESCRIPTION
+}
+
+impl_lint_pass!(NeedlessElse => [NEEDLESS_ELSE]);
+
+/// Returns `true` if moving the statements of `block` out of it could change the meaning of
+/// the code that follows, because they declare bindings or items.
+fn declares_names(block: &Block) -> bool {
+    block
+        .stmts
+        .iter()
+        .any(|stmt| matches!(stmt.kind, StmtKind::Let(_) | StmtKind::Item(_)))
+}
+
+impl NeedlessElse {
+    /// Span of the expression that makes `expr` diverge, if it always does.
+    fn diverging_span(&self, expr: &Expr) -> Option<Span> {
+        if expr.span.from_expansion() {
+            return self
+                .is_diverging_macro(expr.span)
+                .then(|| expr.span.source_callsite());
+        }
+
+        match &expr.kind {
+            ExprKind::Ret(_)
+            | ExprKind::Break(..)
+            | ExprKind::Continue(_)
+            | ExprKind::Become(_)
+            | ExprKind::Yeet(_) => Some(expr.span),
+            ExprKind::Paren(inner) => self.diverging_span(inner),
+            ExprKind::Block(block, None) => self.block_diverging_span(block),
+            ExprKind::If(_, then, Some(els)) => {
+                self.block_diverging_span(then)?;
+                self.diverging_span(els)?;
+                Some(expr.span)
+            }
+            ExprKind::Match(_, arms, _) => (!arms.is_empty()
+                && arms.iter().all(|arm| {
+                    arm.body
+                        .as_ref()
+                        .is_none_or(|body| self.diverging_span(body).is_some())
+                }))
+            .then_some(expr.span),
+            _ => None,
+        }
+    }
+
+    /// Span of the expression that makes the block diverge, if its last statement always does.
+    fn block_diverging_span(&self, block: &Block) -> Option<Span> {
+        match &block.stmts.last()?.kind {
+            StmtKind::Expr(expr) | StmtKind::Semi(expr) => self.diverging_span(expr),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the span was written as an invocation of one of `diverging_macros`.
+    fn is_diverging_macro(&self, span: Span) -> bool {
+        span.macro_backtrace().last().is_some_and(|expn_data| {
+            matches!(
+                expn_data.kind,
+                ExpnKind::Macro(MacroKind::Bang, name)
+                    if self.config.diverging_macros.iter().any(|m| m == name.as_str())
+            )
+        })
+    }
+
+    /// Checks an `if` statement, `is_tail` telling whether it is the tail expression of its
+    /// block.
+    fn check_if_stmt(&self, cx: &EarlyContext<'_>, stmt: &Stmt, expr: &Expr, is_tail: bool) {
+        let ExprKind::If(_, then, Some(els)) = &expr.kind else {
+            return;
+        };
+        let Some(diverging) = self.block_diverging_span(then) else {
+            return;
+        };
+
+        cx.span_lint(NEEDLESS_ELSE, els.span.with_lo(then.span.hi()), |diag| {
+            diag.primary_message(DESCRIPTION);
+            diag.span_label(diverging, DIVERGES_LABEL);
+            let Some(removal) = else_removal(cx, then, els, stmt.span, is_tail) else {
+                diag.help(HELP_MESSAGE);
+                return;
+            };
+            let applicability = match &els.kind {
+                ExprKind::Block(block, _) if !is_tail && declares_names(block) => {
+                    Applicability::MaybeIncorrect
+                }
+                _ => Applicability::MachineApplicable,
+            };
+            diag.span_suggestion(
+                removal.span,
+                HELP_MESSAGE,
+                removal.replacement,
+                applicability,
+            );
+        });
+    }
+
+    /// Checks `let pat = if cond { diverges } else { value };`.
+    fn check_let(&self, cx: &EarlyContext<'_>, stmt: &Stmt, local: &Local) {
+        let LocalKind::Init(init) = &local.kind else {
+            return;
+        };
+        let ExprKind::If(_, then, Some(els)) = &init.kind else {
+            return;
+        };
+        if !local.attrs.is_empty() || init.span.from_expansion() {
+            return;
+        }
+        let Some(diverging) = self.block_diverging_span(then) else {
+            return;
+        };
+
+        cx.span_lint(NEEDLESS_ELSE, els.span.with_lo(then.span.hi()), |diag| {
+            diag.primary_message(DESCRIPTION);
+            diag.span_label(diverging, DIVERGES_LABEL);
+            let Some(rewrite) = let_rewrite(cx, stmt.span, init, then, els) else {
+                diag.help(LET_HELP_MESSAGE);
+                return;
+            };
+            let applicability = match &els.kind {
+                ExprKind::Block(block, _) if declares_names(block) => Applicability::MaybeIncorrect,
+                _ => Applicability::MachineApplicable,
+            };
+            diag.span_suggestion(stmt.span, LET_HELP_MESSAGE, rewrite, applicability);
+        });
+    }
+}
+
+impl EarlyLintPass for NeedlessElse {
+    fn check_block(&mut self, cx: &EarlyContext<'_>, block: &Block) {
+        let last = block.stmts.len().saturating_sub(1);
+        for (i, stmt) in block.stmts.iter().enumerate() {
+            if stmt.span.from_expansion() {
+                continue;
+            }
+            match &stmt.kind {
+                StmtKind::Expr(expr) => self.check_if_stmt(cx, stmt, expr, i == last),
+                StmtKind::Semi(expr) => self.check_if_stmt(cx, stmt, expr, false),
+                StmtKind::Let(local) => self.check_let(cx, stmt, local),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[test]
+fn ui() {
+    dylint_uitesting::ui_test(env!("CARGO_PKG_NAME"), "ui");
+}
@@ -0,0 +1,193 @@
+use rustc_ast::{Block, Expr, ExprKind, StmtKind, util::classify::expr_requires_semi_to_be_stmt};
+use rustc_lexer::{FrontmatterAllowed, TokenKind, tokenize};
+use rustc_lint::{EarlyContext, LintContext};
+use rustc_span::Span;
+use std::collections::HashSet;
+
+/// Text that replaces the `else` of an `if` statement, from the end of the `then` block on.
+pub struct ElseRemoval {
+    pub span: Span,
+    pub replacement: String,
+}
+
+/// Builds the replacement for ` else { body }` following the `then` block: the body moved out
+/// of the braces and unindented to the level of the `if`, or the `if` of an `else if` on its
+/// own line. `stmt_span` covers the whole statement, including a trailing `;`, and `is_tail`
+/// tells whether the `if` is the tail expression of its block. A trailing `;` of the statement
+/// is kept when the moved code ends in an expression, as it discards the value of a block-like
+/// one like `if b { 1 } else { 2 }` or `match`, and is added when the expression needs one.
+///
+/// Returns `None` if any part has no source snippet.
+pub fn else_removal(
+    cx: &EarlyContext<'_>,
+    then: &Block,
+    els: &Expr,
+    stmt_span: Span,
+    is_tail: bool,
+) -> Option<ElseRemoval> {
+    let source_map = cx.sess().source_map();
+    let indent = source_map.indentation_before(stmt_span)?;
+
+    let (body, tail) = match &els.kind {
+        ExprKind::Block(block, None) => {
+            let inner = block_inner(cx, block)?;
+            let body = reindent(&inner, margin(&inner), &indent, true);
+            let tail = block.stmts.last().and_then(|stmt| match &stmt.kind {
+                StmtKind::Expr(tail) => Some(tail.as_ref()),
+                _ => None,
+            });
+            (body, tail)
+        }
+        ExprKind::If(..) => (
+            format!("{indent}{}", source_map.span_to_snippet(els.span).ok()?),
+            Some(els),
+        ),
+        _ => return None,
+    };
+    let needs_semi = !is_tail
+        && tail
+            .is_some_and(|tail| tail.span.from_expansion() || expr_requires_semi_to_be_stmt(tail));
+
+    let body = body.trim_matches('\n').trim_end();
+    let mut replacement = if body.is_empty() {
+        String::new()
+    } else {
+        format!("\n{body}")
+    };
+    let has_semi = stmt_span.hi() > els.span.hi();
+    let end = if tail.is_some() && has_semi {
+        els.span
+    } else {
+        stmt_span
+    };
+    if needs_semi && !has_semi {
+        replacement.push(';');
+    }
+
+    Some(ElseRemoval {
+        span: end.with_lo(then.span.hi()),
+        replacement,
+    })
+}
+
+/// Builds the replacement for `let pat = if cond { diverges } else { stmts; value };`: the `if`
+/// on its own, followed by the `else` statements unindented and `let pat = value;`.
+///
+/// Returns `None` if the `else` block has no tail expression, its tail expression is expanded
+/// from a macro, whose span says nothing about where it was written, or any part has no
+/// source snippet.
+pub fn let_rewrite(
+    cx: &EarlyContext<'_>,
+    stmt_span: Span,
+    init: &Expr,
+    then: &Block,
+    els: &Expr,
+) -> Option<String> {
+    let source_map = cx.sess().source_map();
+    let snippet = |span: Span| source_map.span_to_snippet(span).ok();
+    let indent = source_map.indentation_before(stmt_span)?;
+
+    let (stmts, value) = match &els.kind {
+        ExprKind::Block(block, None) => {
+            let Some(StmtKind::Expr(tail)) = block.stmts.last().map(|stmt| &stmt.kind) else {
+                return None;
+            };
+            if tail.span.from_expansion() {
+                return None;
+            }
+            let inner = block_inner(cx, block)?;
+            let margin = margin(&inner);
+            let tail_offset = (tail.span.lo() - block.span.lo()).0 as usize - 1;
+            let stmts = reindent(inner.get(..tail_offset)?, margin, &indent, true);
+            let value = reindent(&snippet(tail.span)?, margin, &indent, false);
+            (stmts, value)
+        }
+        ExprKind::If(..) => (String::new(), snippet(els.span)?),
+        _ => return None,
+    };
+
+    let if_head = snippet(init.span.with_hi(then.span.hi()))?;
+    let let_head = snippet(stmt_span.with_hi(init.span.lo()))?;
+    let semi = if snippet(stmt_span)?.ends_with(';') {
+        ";"
+    } else {
+        ""
+    };
+    let mut rewrite = if_head;
+    let stmts = stmts.trim_matches('\n').trim_end();
+    if !stmts.is_empty() {
+        rewrite.push('\n');
+        rewrite.push_str(stmts);
+    }
+    rewrite.push_str(&format!("\n{indent}{let_head}{value}{semi}"));
+    Some(rewrite)
+}
+
+/// Source of a block between its braces.
+fn block_inner(cx: &EarlyContext<'_>, block: &Block) -> Option<String> {
+    let snippet = cx.sess().source_map().span_to_snippet(block.span).ok()?;
+    Some(snippet.strip_prefix('{')?.strip_suffix('}')?.to_string())
+}
+
+/// Lines of `text`, each paired with whether it starts inside a multi-line string literal,
+/// whose contents must be kept as written.
+fn source_lines(text: &str) -> Vec<(&str, bool)> {
+    let mut literal_line_starts = HashSet::new();
+    let mut offset = 0;
+    for token in tokenize(text, FrontmatterAllowed::No) {
+        let end = offset + token.len as usize;
+        if let TokenKind::Literal { .. } = token.kind {
+            let newlines = text[offset..end].match_indices('\n');
+            literal_line_starts.extend(newlines.map(|(index, _)| offset + index + 1));
+        }
+        offset = end;
+    }
+    let line_starts =
+        std::iter::once(0).chain(text.match_indices('\n').map(|(index, _)| index + 1));
+    text.split('\n')
+        .zip(line_starts)
+        .map(|(line, start)| (line, literal_line_starts.contains(&start)))
+        .collect()
+}
+
+/// Smallest indentation of the lines of a block body, ignoring the part on the line of the
+/// opening brace, blank lines and lines inside string literals.
+fn margin(inner: &str) -> usize {
+    source_lines(inner)
+        .into_iter()
+        .skip(1)
+        .filter(|(line, in_literal)| !in_literal && !line.trim().is_empty())
+        .map(|(line, _)| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0)
+}
+
+/// Moves the lines of `text` from `margin` columns of indentation to `indent`. The first line
+/// is indented only if it starts a line of the result. Lines inside string literals are kept
+/// as written, as their indentation is part of the string.
+fn reindent(text: &str, margin: usize, indent: &str, first_starts_line: bool) -> String {
+    source_lines(text)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (line, in_literal))| {
+            if in_literal {
+                return line.to_string();
+            }
+            if line.trim().is_empty() {
+                return String::new();
+            }
+            if i == 0 {
+                let line = line.trim();
+                return if first_starts_line {
+                    format!("{indent}{line}")
+                } else {
+                    line.to_string()
+                };
+            }
+            let line = line.trim_end();
+            let leading = line.len() - line.trim_start().len();
+            format!("{indent}{}", &line[leading.min(margin)..])
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,210 @@
+//@edition:2024
+#![allow(unused, clippy::needless_return, clippy::redundant_else)]
+
+fn tail_position(x: i32) -> i32 {
+    if x < 0 {
+        return 0;
+    //~v ERROR: `else` after a branch that always diverges
+    } else {
+        let doubled = x * 2;
+        doubled + 1
+    }
+}
+
+fn in_loop(values: &[i32]) -> i32 {
+    let mut total = 0;
+    for v in values {
+        if *v < 0 {
+            continue;
+        //~v ERROR: `else` after a branch that always diverges
+        } else {
+            total += v;
+        }
+        total += 1;
+    }
+    total
+}
+
+fn value_tail_needs_semicolon(values: &[i32]) {
+    for v in values {
+        if *v == 0 {
+            break;
+        //~v ERROR: `else` after a branch that always diverges
+        } else {
+            println!("{v}")
+        }
+        println!("next");
+    }
+}
+
+fn with_semicolon(x: i32) -> i32 {
+    let mut y = x;
+    if y > 10 {
+        return y;
+    //~v ERROR: `else` after a branch that always diverges
+    } else {
+        y += 1;
+    };
+    y
+}
+
+fn diverging_macro(x: Option<i32>) -> i32 {
+    if x.is_none() {
+        panic!("missing value")
+    //~v ERROR: `else` after a branch that always diverges
+    } else {
+        x.unwrap_or_default()
+    }
+}
+
+fn diverging_match(kind: u8, x: i32) -> i32 {
+    if x < 0 {
+        match kind {
+            0 => return 0,
+            1 => return -1,
+            _ => unreachable!("unknown kind"),
+        }
+    //~v ERROR: `else` after a branch that always diverges
+    } else {
+        x * 2
+    }
+}
+
+fn if_let_chain(input: Option<&str>) -> usize {
+    if let Some(input) = input
+        && input.is_empty()
+    {
+        return 0;
+    //~v ERROR: `else` after a branch that always diverges
+    } else {
+        input.map_or(1, str::len)
+    }
+}
+
+fn else_if(a: bool, b: bool) -> i32 {
+    if a {
+        return 1;
+    //~v ERROR: `else` after a branch that always diverges
+    } else if b {
+        2
+    } else {
+        3
+    }
+}
+
+fn let_form(input: &str) -> Option<usize> {
+    let len = if input.is_empty() {
+        return None;
+    //~v ERROR: `else` after a branch that always diverges
+    } else {
+        input.len()
+    };
+    Some(len)
+}
+
+fn let_form_with_statements(input: &str) -> Option<usize> {
+    let len: usize = if input.is_empty() {
+        return None;
+    //~v ERROR: `else` after a branch that always diverges
+    } else {
+        let trimmed = input.trim();
+        trimmed
+            .len()
+    };
+    Some(len)
+}
+
+fn not_diverging(x: i32) -> i32 {
+    if x < 0 {
+        println!("negative");
+    } else {
+        println!("positive");
+    }
+    x
+}
+
+fn conditionally_diverging(x: i32) -> i32 {
+    if x < 0 {
+        if x < -10 {
+            return 0;
+        }
+    } else {
+        println!("positive");
+    }
+    x
+}
+
+fn labeled_block(x: i32) -> i32 {
+    if x < 0 {
+        'done: {
+            break 'done;
+        }
+    } else {
+        println!("positive");
+    }
+    x
+}
+
+fn expression_position(x: i32) -> i32 {
+    std::convert::identity(if x < 0 { return 0 } else { x })
+}
+
+fn main() {}
+
+fn multiline_string_literals(x: i32) -> String {
+    if x < 0 {
+        return String::new();
+    //~v ERROR: `else` after a branch that always diverges
+    } else {
+        let text = "first
+    second
+third";
+        format!(
+            r#"{text}
+  raw"#
+        )
+    }
+}
+
+fn value_else_if_with_semicolon(a: bool, b: bool) {
+    if a {
+        return;
+    //~v ERROR: `else` after a branch that always diverges
+    } else if b {
+        1
+    } else {
+        2
+    };
+    println!("done");
+}
+
+fn value_match_tail_with_semicolon(a: bool, x: i32) {
+    if a {
+        return;
+    //~v ERROR: `else` after a branch that always diverges
+    } else {
+        let y = x + 1;
+        match y {
+            0 => "zero",
+            _ => "other",
+        }
+    };
+    println!("done");
+}
+
+macro_rules! add_one {
+    ($x:expr) => {
+        $x + 1
+    };
+}
+
+fn let_form_macro_tail(input: &str) -> Option<usize> {
+    let len = if input.is_empty() {
+        return None;
+    //~v ERROR: `else` after a branch that always diverges
+    } else {
+        let trimmed = input.trim();
+        add_one!(trimmed.len())
+    };
+    Some(len)
+}
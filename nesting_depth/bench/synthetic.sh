@@ -0,0 +1,218 @@
+#!/usr/bin/env bash
+# Generates a synthetic crate of roughly LINES lines (default 100k) and times the
+# nesting_depth lint on it with `cargo dylint` as built from BASE and from REV (default HEAD),
+# along with a plain `cargo check` without the lint. Each build is timed RUNS times (default 5),
+# the builds taking turns, and the median wall time, peak RSS and time to the first warning of
+# each are printed as a markdown table, which is also appended to bench_output.txt.
+#
+# Each revision is checked out into a temporary git worktree, and the lint is built and run
+# once before it is timed, so only the check of the crate is measured. Both revisions run with
+# the configuration written to the crate's dylint.toml, whatever their defaults. The script
+# fails if a build or lint run fails, if its peak RSS cannot be found in the output, or if the
+# runs of a build report different warning counts.
+#
+# The numbers in the README were measured on a single-core Linux machine, where `cargo dylint`
+# was a wrapper that loads the lint into a rustc driver.
+#
+# usage: nesting_depth/bench/synthetic.sh BASE [REV] [LINES] [RUNS]
+set -euo pipefail
+
+base="${1:?usage: $0 BASE [REV] [LINES] [RUNS]}"
+rev="${2:-HEAD}"
+lines="${3:-100000}"
+runs="${4:-5}"
+root="$(cd "$(dirname "$0")/../.." && pwd)"
+crate="$root/target/nesting_depth_bench"
+output="$root/bench_output.txt"
+
+# Each generated module holds one function of this many lines.
+fn_lines=40
+fns=$((lines / fn_lines))
+
+mkdir -p "$crate/src"
+cat > "$crate/Cargo.toml" <<TOML
+[package]
+name = "nesting_depth_bench"
+version = "0.0.0"
+edition = "2024"
+publish = false
+
+[workspace]
+TOML
+
+cat > "$crate/dylint.toml" <<TOML
+[nesting_depth]
+max_depth = 3
+max_closure_depth = 2
+debug = false
+TOML
+
+{
+  echo "#![allow(unused, clippy::all)]"
+  for ((i = 0; i < fns; i++)); do
+    cat <<RUST
+pub mod m$i {
+    pub fn f$i(v: i32, items: &[i32]) -> i32 {
+        let mut total = 0;
+        for item in items {
+            match item % 4 {
+                0 => {
+                    if v > 0 {
+                        if v > 1 {
+                            if v > 2 {
+                                total += item;
+                            }
+                        }
+                    }
+                }
+                1 => total -= 1,
+                _ => {}
+            }
+        }
+        let add = |x: i32| {
+            let inner = |y: i32| x + y;
+            inner(v)
+        };
+        if v == 0 {
+            total += add(0);
+        } else if v == 1 {
+            total += add(1);
+        } else if v == 2 {
+            total += add(2);
+        } else {
+            total += add(3);
+        }
+        while total > 1000 {
+            total /= 2;
+        }
+        let s = format!("{total}");
+        if s.is_empty() {
+            return 0;
+        }
+        total
+    }
+}
+RUST
+  done
+} > "$crate/src/lib.rs"
+
+echo "generated $(wc -l < "$crate/src/lib.rs") lines in $crate/src/lib.rs"
+
+# GNU time reports peak RSS in kilobytes with `-v`, BSD/macOS time in bytes with `-l`.
+time_flag=-v
+rss_pattern="Maximum resident set size"
+rss_unit=1024
+if ! /usr/bin/time -v true > /dev/null 2>&1; then
+  time_flag=-l
+  rss_pattern="maximum resident set size"
+  rss_unit=1048576
+fi
+
+# Diagnostics of the lint, which the plain check does not emit.
+warning_pattern='^warning: ([a-z ]*nesting depth|consecutive|match arm)'
+
+worktrees="$(mktemp -d)"
+results="$(mktemp -d)"
+log="$(mktemp)"
+cleanup() {
+  for worktree in "$worktrees"/*; do
+    if [ -d "$worktree" ]; then
+      git -C "$root" worktree remove --force "$worktree"
+    fi
+  done
+  rm -rf "$worktrees" "$results" "$log"
+}
+trap cleanup EXIT
+
+# Fails with the output of the last run.
+fail() {
+  cat "$log" >&2
+  echo "nesting_depth bench: $1" >&2
+  exit 1
+}
+
+# Checks the bench crate again with `$@` and appends the wall time and time to the first
+# warning in seconds, peak RSS in MiB and warning count of the run to the results of build $1.
+# The output is timestamped by perl as it arrives, as warnings are printed while the check is
+# still running.
+measure() {
+  local build wall first rss warnings
+  build="$1"
+  shift
+  touch "$crate/src/lib.rs"
+  if ! CARGO_INCREMENTAL=0 /usr/bin/time "$time_flag" "$@" 2>&1 \
+    | perl -MTime::HiRes=time -e '
+        my ($start, $first) = (time, "-");
+        while (my $line = <STDIN>) {
+          print $line;
+          $first = sprintf "%.2f", time - $start if $first eq "-" && $line =~ /$ARGV[0]/;
+        }
+        printf "bench: %.2f %s\n", time - $start, $first;' "$warning_pattern" > "$log"; then
+    fail "$* failed"
+  fi
+
+  read -r _ wall first < <(grep "^bench: " "$log" | tail -n 1)
+  rss="$(grep "$rss_pattern" "$log" | grep -oE "[0-9]+" | head -n 1)" || fail "no peak RSS for $build"
+  warnings="$(grep -cE "$warning_pattern" "$log")" || true
+  echo "$wall $first $((rss / rss_unit)) $warnings" >> "$results/$build"
+}
+
+# Median of column $1 of the results of build $2, printed with format $3, or `-` if the column
+# has no numbers.
+median() {
+  awk -v column="$1" '$column != "-" { print $column }' "$results/$2" | sort -n \
+    | awk -v format="$3" '{ v[NR] = $1 }
+        END {
+          if (NR == 0) print "-"
+          else if (NR % 2) printf format "\n", v[(NR + 1) / 2]
+          else printf format "\n", (v[NR / 2] + v[NR / 2 + 1]) / 2
+        }'
+}
+
+# Checks out commit $1 into a worktree and builds and runs the lint from it once, so that the
+# timed runs only check the crate.
+prepare() {
+  local worktree="$worktrees/$1"
+  git -C "$root" worktree add --detach "$worktree" "$1" > /dev/null
+  CARGO_INCREMENTAL=0 cargo dylint --path "$worktree/nesting_depth" \
+    --manifest-path "$crate/Cargo.toml" > "$log" 2>&1 || fail "building $1 failed"
+}
+
+base_sha="$(git -C "$root" rev-parse --short "$base")"
+rev_sha="$(git -C "$root" rev-parse --short "$rev")"
+cargo check --manifest-path "$crate/Cargo.toml" > "$log" 2>&1 || fail "cargo check failed"
+prepare "$base_sha"
+prepare "$rev_sha"
+
+for ((run = 1; run <= runs; run++)); do
+  echo "run $run of $runs"
+  measure check cargo check --manifest-path "$crate/Cargo.toml"
+  for sha in "$base_sha" "$rev_sha"; do
+    measure "$sha" cargo dylint --path "$worktrees/$sha/nesting_depth" \
+      --manifest-path "$crate/Cargo.toml"
+  done
+done
+
+{
+  echo "== nesting_depth synthetic bench: $(date -u +%Y-%m-%dT%H:%M:%SZ) lines=$lines runs=$runs"
+  echo "| Build | Wall time | Peak RSS | First warning after | Warnings |"
+  echo "|---|---|---|---|---|"
+  for build in check "$base_sha" "$rev_sha"; do
+    case "$build" in
+      check) label="plain \`cargo check\`, no lint" ;;
+      "$base_sha") label="BASE ($base_sha)" ;;
+      *) label="REV ($rev_sha)" ;;
+    esac
+    if [ "$(awk '{ print $4 }' "$results/$build" | sort -u | wc -l)" -ne 1 ]; then
+      fail "runs of $label reported different warning counts"
+    fi
+    warnings="$(awk 'NR == 1 { print $4 }' "$results/$build")"
+    first="$(median 2 "$build" "%.2f")"
+    if [ "$first" != "-" ]; then
+      first="$first s"
+    fi
+    wall="$(median 1 "$build" "%.2f") s"
+    rss="$(median 3 "$build" "%.0f") MiB"
+    echo "| $label | $wall | $rss | $first | $warnings |"
+  done
+} | tee -a "$output"
@@ -5,20 +5,89 @@ use crate::debug::SpanRange;
 
 pub const HELP_MESSAGE: &str = "use early returns and guard clauses to reduce nesting";
 
+pub const CONSEC_IF_ELSE_HELP_MESSAGE: &str =
+    "use a `match` or table-driven dispatch (a lookup table or map of handlers) instead";
+
+pub const CLOSURE_DEPTH_HELP_MESSAGE: &str = "extract nested closures into named functions";
+
+pub const MATCH_ARMS_HELP_MESSAGE: &str =
+    "group related arms, or dispatch through a lookup table or trait instead";
+
+pub const ARM_ITEMS_HELP_MESSAGE: &str = "move the arm body into a separate function";
+
+pub const ITEM_NESTING_HELP_MESSAGE: &str =
+    "move nested items up to module level or into their own file";
+
+pub const EXPR_DEPTH_HELP_MESSAGE: &str =
+    "bind intermediate results to local variables with descriptive names";
+
+pub const MATCH_SUGGESTION_MESSAGE: &str = "convert this if-else chain into a `match`";
+
 /// Default maximum nesting levels
 const DEFAULT_MAX_DEPTH: usize = 3;
 
 /// Default ignore closures when counting depth
 const DEFAULT_IGNORE_CLOSURES: bool = true;
 
+/// Default maximum closures nested within each other
+const DEFAULT_MAX_CLOSURE_DEPTH: usize = 2;
+
+/// Default allowance for a closure passed to an iterator adaptor inside another one
+const DEFAULT_ALLOW_ITERATOR_ADAPTORS: bool = true;
+
 /// Default maximum items in an if-then block
 const DEFAULT_MAX_THEN_ITEMS: usize = 20;
 
 /// Default maximum consecutive if-else statements
 const DEFAULT_MAX_CONSEC_IF_ELSE: usize = 10;
 
+/// Default maximum items (inline modules, impls, traits, fns, consts and statics) nested
+/// within each other
+const DEFAULT_MAX_ITEM_NESTING: usize = 4;
+
+/// Default maximum arms in a single match
+const DEFAULT_MAX_MATCH_ARMS: usize = 25;
+
+/// Default maximum items in a single match arm body
+const DEFAULT_MAX_ARM_ITEMS: usize = 20;
+
+/// Default maximum call, method call, struct, array and tuple expressions nested within each
+/// other
+const DEFAULT_MAX_EXPR_DEPTH: usize = 4;
+
+/// Default compiler pass
+const DEFAULT_PASS: Pass = Pass::Early;
+
+/// Default over-depth regions to report
+const DEFAULT_REPORT: ReportMode = ReportMode::EveryRegion;
+
+/// Default trace recording: on in debug builds of the lint
 const DEFAULT_DEBUG: bool = cfg!(debug_assertions);
 
+/// Compiler pass the lint runs in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pass {
+    /// Walk the AST
+    Early,
+    /// Walk the HIR, where `?`, `for`/`while` loops, `.await` and async fn bodies are
+    /// recognizable desugarings and are excluded precisely
+    Late,
+}
+
+/// Which over-depth regions to report for each item body. A region is a maximal subtree
+/// whose contexts all exceed the depth budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportMode {
+    /// Only the first region
+    First,
+    /// Only the deepest region
+    Deepest,
+    /// Every region, each with its own diagnostic
+    EveryRegion,
+}
+
 /// Lint configuration
 #[serde_inline_default]
 #[derive(Deserialize)]
@@ -31,6 +100,16 @@ pub struct Config {
     #[serde_inline_default(DEFAULT_IGNORE_CLOSURES)]
     pub ignore_closures: bool,
 
+    /// Maximum allowed closures (including async closures and async blocks) nested within
+    /// each other, counted separately from `max_depth`
+    #[serde_inline_default(DEFAULT_MAX_CLOSURE_DEPTH)]
+    pub max_closure_depth: usize,
+
+    /// Allow a closure passed to an iterator adaptor or consumer (`map`, `filter`, `fold`, ...)
+    /// inside another closure passed to one, even if that exceeds `max_closure_depth`
+    #[serde_inline_default(DEFAULT_ALLOW_ITERATOR_ADAPTORS)]
+    pub allow_iterator_adaptors: bool,
+
     /// Maximum allowed items in an if-then block
     #[serde_inline_default(DEFAULT_MAX_THEN_ITEMS)]
     pub max_then_items: usize,
@@ -39,11 +118,37 @@ pub struct Config {
     #[serde_inline_default(DEFAULT_MAX_CONSEC_IF_ELSE)]
     pub max_consec_if_else: usize,
 
-    /// Enable debug output
+    /// Maximum allowed items (inline modules, impls, traits, fns, consts and statics) nested
+    /// within each other, counted separately from `max_depth`
+    #[serde_inline_default(DEFAULT_MAX_ITEM_NESTING)]
+    pub max_item_nesting: usize,
+
+    /// Maximum allowed arms in a single match
+    #[serde_inline_default(DEFAULT_MAX_MATCH_ARMS)]
+    pub max_match_arms: usize,
+
+    /// Maximum allowed items (statements + expr) in a single match arm body
+    #[serde_inline_default(DEFAULT_MAX_ARM_ITEMS)]
+    pub max_arm_items: usize,
+
+    /// Maximum allowed call, method call, struct, array and tuple expressions nested within
+    /// each other, reported by `expr_nesting_depth`
+    #[serde_inline_default(DEFAULT_MAX_EXPR_DEPTH)]
+    pub max_expr_depth: usize,
+
+    /// Which over-depth regions to report for each item body
+    #[serde_inline_default(DEFAULT_REPORT)]
+    pub report: ReportMode,
+
+    /// Compiler pass to run the lint in
+    #[serde_inline_default(DEFAULT_PASS)]
+    pub pass: Pass,
+
+    /// Record and log a trace of context PUSH/POP events
     #[serde_inline_default(DEFAULT_DEBUG)]
     pub debug: bool,
 
-    /// Optional span range to limit debug output
+    /// Optional span range to limit the debug trace to, and to render the context tree of
     #[serde(default)]
     pub debug_span_range: Option<SpanRange>,
 
@@ -59,8 +164,16 @@ impl Default for Config {
         Self {
             max_depth: DEFAULT_MAX_DEPTH,
             ignore_closures: DEFAULT_IGNORE_CLOSURES,
+            max_closure_depth: DEFAULT_MAX_CLOSURE_DEPTH,
+            allow_iterator_adaptors: DEFAULT_ALLOW_ITERATOR_ADAPTORS,
             max_then_items: DEFAULT_MAX_THEN_ITEMS,
             max_consec_if_else: DEFAULT_MAX_CONSEC_IF_ELSE,
+            max_item_nesting: DEFAULT_MAX_ITEM_NESTING,
+            max_match_arms: DEFAULT_MAX_MATCH_ARMS,
+            max_arm_items: DEFAULT_MAX_ARM_ITEMS,
+            max_expr_depth: DEFAULT_MAX_EXPR_DEPTH,
+            report: DEFAULT_REPORT,
+            pass: DEFAULT_PASS,
             debug: DEFAULT_DEBUG,
             debug_span_range: None,
             ignore_macros: Vec::new(),
@@ -1,11 +1,57 @@
 use rustc_ast::NodeId;
-use rustc_span::Span;
+use rustc_lint::Lint;
+use rustc_span::{Span, symbol::Ident};
 
-use crate::config::Config;
+use crate::config::{
+    ARM_ITEMS_HELP_MESSAGE, CLOSURE_DEPTH_HELP_MESSAGE, CONSEC_IF_ELSE_HELP_MESSAGE, Config,
+    EXPR_DEPTH_HELP_MESSAGE, HELP_MESSAGE, ITEM_NESTING_HELP_MESSAGE, MATCH_ARMS_HELP_MESSAGE,
+};
+use crate::{EXPR_NESTING_DEPTH, NESTING_DEPTH};
+
+/// Iterator adaptors taking a closure, which return another iterator.
+const ITERATOR_ADAPTORS: &[&str] = &[
+    "filter",
+    "filter_map",
+    "flat_map",
+    "inspect",
+    "map",
+    "map_while",
+    "scan",
+    "skip_while",
+    "take_while",
+];
+
+/// Iterator consumers taking a closure, which run the iterator to produce a value.
+const ITERATOR_CONSUMERS: &[&str] = &[
+    "all",
+    "any",
+    "find",
+    "find_map",
+    "fold",
+    "for_each",
+    "max_by",
+    "max_by_key",
+    "min_by",
+    "min_by_key",
+    "partition",
+    "position",
+    "rposition",
+    "try_fold",
+    "try_for_each",
+];
+
+/// Returns `true` if a method with this name is an iterator adaptor or consumer taking a
+/// closure, whose closures may nest two deep when `allow_iterator_adaptors` is set.
+pub fn is_iterator_method(method: &str) -> bool {
+    ITERATOR_ADAPTORS.contains(&method) || ITERATOR_CONSUMERS.contains(&method)
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ContextKind {
     Func,
+    Const,
+    Static,
+    ConstBlock,
     Mod,
     Trait,
     Impl,
@@ -14,7 +60,9 @@ pub enum ContextKind {
     ElseIf,
     Else,
     Match,
+    Arm,
     Closure,
+    AsyncBlock,
     Block,
     ExprBlock,
     While,
@@ -26,6 +74,9 @@ impl ContextKind {
     pub fn count_depth(&self, config: &Config) -> bool {
         match self {
             ContextKind::Func => true,
+            ContextKind::Const => true,
+            ContextKind::Static => true,
+            ContextKind::ConstBlock => true,
             ContextKind::Mod => false,
             ContextKind::Trait => false,
             ContextKind::Impl => false,
@@ -34,7 +85,9 @@ impl ContextKind {
             ContextKind::ElseIf => false,
             ContextKind::Else => false,
             ContextKind::Match => true,
+            ContextKind::Arm => false,
             ContextKind::Closure => !config.ignore_closures,
+            ContextKind::AsyncBlock => !config.ignore_closures,
             ContextKind::Block => true,
             ContextKind::ExprBlock => true,
             ContextKind::While => true,
@@ -43,13 +96,33 @@ impl ContextKind {
         }
     }
 
-    pub fn is_if_or_if_branch(&self) -> bool {
+    /// Item bodies that start a fresh depth count: functions, const/static initializers and
+    /// `const` blocks.
+    pub fn is_item_root(&self) -> bool {
+        matches!(
+            self,
+            ContextKind::Func | ContextKind::Const | ContextKind::Static | ContextKind::ConstBlock
+        )
+    }
+
+    /// Items, which share the item nesting budget.
+    pub fn is_item(&self) -> bool {
         matches!(
             self,
-            ContextKind::If | ContextKind::Then | ContextKind::ElseIf | ContextKind::Else
+            ContextKind::Func
+                | ContextKind::Const
+                | ContextKind::Static
+                | ContextKind::Mod
+                | ContextKind::Trait
+                | ContextKind::Impl
         )
     }
 
+    /// Closures, async closures and async blocks, which share the closure depth budget.
+    pub fn is_closure(&self) -> bool {
+        matches!(self, ContextKind::Closure | ContextKind::AsyncBlock)
+    }
+
     pub fn is_if_branch(&self) -> bool {
         matches!(
             self,
@@ -62,6 +135,11 @@ impl ContextKind {
 pub enum Reason {
     Depth(usize),
     ConsecIfElse(usize),
+    ClosureDepth(usize),
+    MatchArms(usize),
+    ArmItems(usize),
+    ItemNesting(usize),
+    ExprDepth(usize),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -70,27 +148,100 @@ pub struct NestingLint {
     pub span: Span,
     pub kind: ContextKind,
     pub reason: Reason,
+    /// Name of the item the depth is counted from, if it is nested in another item's body.
+    pub item: Option<Ident>,
+    /// Counted contexts from the outermost to the deepest one reached.
+    pub path: Vec<(ContextKind, Span)>,
+    /// Pattern of the innermost match arm the linted context is nested in.
+    pub arm: Option<Span>,
+    /// Replacement for the linted span, offered as a `MaybeIncorrect` suggestion.
+    pub suggestion: Option<String>,
+}
+
+/// An over-budget subtree, tracked from its outermost context until that context pops.
+#[derive(Debug, Clone)]
+pub struct Region<Id = NodeId> {
+    /// Stack index of the item root the region is counted from.
+    pub root: usize,
+    /// Id of the outermost context of the region.
+    pub id: Id,
+    pub lint: NestingLint,
+}
+
+impl<Id> Region<Id> {
+    /// Opens a region rooted at the context `id`, or records a deeper point in the region
+    /// already open under the same item root. A nested item's region is kept apart from the
+    /// open region of the item it is nested in.
+    pub fn track(regions: &mut Vec<Region<Id>>, root: usize, id: Id, lint: NestingLint) {
+        let Some(open) = regions.iter_mut().find(|region| region.root == root) else {
+            regions.push(Region { root, id, lint });
+            return;
+        };
+        if lint.reason.count() > open.lint.reason.count() {
+            open.lint.reason = lint.reason;
+            open.lint.path = lint.path;
+        }
+    }
+}
+
+/// A call, method call, struct, array or tuple expression, counted towards `max_expr_depth`.
+#[derive(Debug, Clone)]
+pub struct ExprLayer<Id = NodeId> {
+    pub id: Id,
+    pub span: Span,
+    /// Number of contexts on the stack when the layer was entered. Layers only nest within
+    /// the same context, so a block, closure or branch starts a fresh count.
+    pub context: usize,
+    pub depth: usize,
+    /// Receiver of a method call, which continues the call's layer instead of nesting in it.
+    pub receiver: Option<Id>,
+}
+
+/// A closure, async closure or async block, counted towards `max_closure_depth` apart from
+/// the contexts, since a closure only counts towards `max_depth` when its body has statements.
+#[derive(Debug, Clone)]
+pub struct ClosureFrame<Id = NodeId> {
+    pub id: Id,
+    pub kind: ContextKind,
+    pub span: Span,
+    /// Number of contexts on the stack when the closure was entered.
+    pub context: usize,
+    /// Whether this closure is passed directly to an iterator adaptor.
+    pub iterator_adaptor: bool,
 }
 
 #[derive(Clone)]
-pub struct Context {
+pub struct Context<Id = NodeId> {
     pub span: Span,
-    pub id: NodeId,
+    pub id: Id,
     pub kind: ContextKind,
+    /// Name of the item, for item root contexts.
+    pub ident: Option<Ident>,
+    /// Initializer expression of a const/static item or const block, which is the item's
+    /// body rather than a nested block.
+    pub body_id: Option<Id>,
     /// Count of consecutive if/else-if/else branches in the current block.
     pub consec_if_else_count: usize,
     /// Count of consecutive if/else-if branches in the current if-else chain.
     pub consec_if_branch_count: usize,
+    /// `match` rewrite of the if-else chain rooted at this context, if it has one.
+    pub match_suggestion: Option<String>,
+    /// Closed over-budget regions inside this item root, reported when it pops.
+    pub regions: Vec<NestingLint>,
 }
 
-impl Context {
-    pub fn new(kind: ContextKind, id: NodeId, span: Span) -> Self {
+impl<Id> Context<Id> {
+    pub fn new(kind: ContextKind, id: Id, span: Span) -> Self {
         Self {
             span,
             kind,
             id,
+            ident: None,
+            body_id: None,
             consec_if_else_count: 0,
             consec_if_branch_count: 0,
+            match_suggestion: None,
+            regions: Vec::new(),
         }
     }
 }
@@ -99,12 +250,17 @@ impl ContextKind {
     pub fn descr(&self) -> &'static str {
         match self {
             ContextKind::Func => "func",
+            ContextKind::Const => "const",
+            ContextKind::Static => "static",
+            ContextKind::ConstBlock => "const-block",
             ContextKind::If => "if",
             ContextKind::Then => "then",
             ContextKind::Else => "else",
             ContextKind::ElseIf => "else-if",
             ContextKind::Match => "match",
+            ContextKind::Arm => "arm",
             ContextKind::Closure => "closure",
+            ContextKind::AsyncBlock => "async-block",
             ContextKind::Block => "block",
             ContextKind::ExprBlock => "expr-block",
             ContextKind::While => "while",
@@ -118,10 +274,39 @@ impl ContextKind {
 }
 
 impl Reason {
+    /// The depth or count that exceeded its budget.
+    pub fn count(&self) -> usize {
+        match self {
+            Reason::Depth(count)
+            | Reason::ConsecIfElse(count)
+            | Reason::ClosureDepth(count)
+            | Reason::MatchArms(count)
+            | Reason::ArmItems(count)
+            | Reason::ItemNesting(count)
+            | Reason::ExprDepth(count) => *count,
+        }
+    }
+
     pub fn outer_context_label(&self) -> &'static str {
         match self {
             Reason::Depth(_) => "outer nested context",
             Reason::ConsecIfElse(_) => "first if in sequence",
+            Reason::ClosureDepth(_) => "outermost closure",
+            Reason::MatchArms(_) | Reason::ArmItems(_) => "enclosing match",
+            Reason::ItemNesting(_) => "outermost item",
+            Reason::ExprDepth(_) => "outermost expression",
+        }
+    }
+
+    pub fn help(&self) -> &'static str {
+        match self {
+            Reason::Depth(_) => HELP_MESSAGE,
+            Reason::ConsecIfElse(_) => CONSEC_IF_ELSE_HELP_MESSAGE,
+            Reason::ClosureDepth(_) => CLOSURE_DEPTH_HELP_MESSAGE,
+            Reason::MatchArms(_) => MATCH_ARMS_HELP_MESSAGE,
+            Reason::ArmItems(_) => ARM_ITEMS_HELP_MESSAGE,
+            Reason::ItemNesting(_) => ITEM_NESTING_HELP_MESSAGE,
+            Reason::ExprDepth(_) => EXPR_DEPTH_HELP_MESSAGE,
         }
     }
 
@@ -129,31 +314,56 @@ impl Reason {
         match self {
             Reason::Depth(_) => "nesting depth",
             Reason::ConsecIfElse(_) => "consecutive if-else statements",
+            Reason::ClosureDepth(_) => "closure nesting depth",
+            Reason::MatchArms(_) => "match arms",
+            Reason::ArmItems(_) => "match arm items",
+            Reason::ItemNesting(_) => "item nesting depth",
+            Reason::ExprDepth(_) => "expression nesting depth",
+        }
+    }
+
+    /// Lint the reason is reported under.
+    pub fn lint(&self) -> &'static Lint {
+        match self {
+            Reason::ExprDepth(_) => EXPR_NESTING_DEPTH,
+            _ => NESTING_DEPTH,
+        }
+    }
+
+    /// Configured budget the depth or count is checked against.
+    fn max(&self, config: &Config) -> usize {
+        match self {
+            Reason::Depth(_) => config.max_depth,
+            Reason::ConsecIfElse(_) => config.max_consec_if_else,
+            Reason::ClosureDepth(_) => config.max_closure_depth,
+            Reason::MatchArms(_) => config.max_match_arms,
+            Reason::ArmItems(_) => config.max_arm_items,
+            Reason::ItemNesting(_) => config.max_item_nesting,
+            Reason::ExprDepth(_) => config.max_expr_depth,
         }
     }
 
     pub fn message(&self, config: &Config) -> String {
         let label = self.label();
-        match self {
-            Reason::Depth(depth) => {
-                let max_1 = config.max_depth + 1;
-                let levels_desc = if *depth > max_1 {
-                    format!("{max_1} to {depth} levels")
-                } else {
-                    format!("{depth} levels")
-                };
-                format!(
-                    "{label}: {max} max allowed, {levels_desc} found",
-                    max = config.max_depth,
-                )
+        let max = self.max(config);
+        let count = self.count();
+        let found = match self {
+            Reason::ConsecIfElse(_) | Reason::MatchArms(_) | Reason::ArmItems(_) => {
+                count.to_string()
             }
-            Reason::ConsecIfElse(count) => {
-                format!(
-                    "{label}: {max} max allowed, {count} found",
-                    max = config.max_consec_if_else,
-                )
-            }
-        }
+            _ => levels_desc(max, count),
+        };
+        format!("{label}: {max} max allowed, {found} found")
+    }
+}
+
+/// Describes the levels found beyond `max`, e.g. `4 levels` or `4 to 6 levels`.
+fn levels_desc(max: usize, depth: usize) -> String {
+    let max_1 = max + 1;
+    if depth > max_1 {
+        format!("{max_1} to {depth} levels")
+    } else {
+        format!("{depth} levels")
     }
 }
 
@@ -1,13 +1,17 @@
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    fmt::{Debug, Display, Write},
+};
 
-use rustc_ast::ExprKind;
+use log::{debug, log_enabled, trace};
+use rustc_ast::{ExprKind, NodeId};
 use rustc_lint::{EarlyContext, LintContext};
 use rustc_span::{RemapPathScopeComponents, Span, source_map::SourceMap};
 use serde::Deserialize;
 
-use crate::NestingDepth;
+use crate::{NestingDepth, context::ContextKind, stack::ContextStack};
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
 pub struct SpanRange {
     file: String,
     start_line: usize,
@@ -27,9 +31,77 @@ impl SpanRange {
     }
 }
 
+/// Change to the context stack recorded in the debug trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEventKind {
+    /// A context was pushed onto the stack.
+    Push,
+    /// A context was popped off the stack.
+    Pop,
+}
+
+impl std::fmt::Display for TraceEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraceEventKind::Push => write!(f, "PUSH"),
+            TraceEventKind::Pop => write!(f, "POP"),
+        }
+    }
+}
+
+/// A single PUSH or POP of a context, logged as one `key=value` line and kept so that
+/// the context tree can be rendered once the crate has been visited.
+#[derive(Debug, Clone)]
+pub struct TraceEvent<Id = NodeId> {
+    pub event: TraceEventKind,
+    pub id: Id,
+    pub kind: ContextKind,
+    /// Counted nesting depth after a push, or before a pop.
+    pub depth: usize,
+    /// `file:line:col` of the start of the context span.
+    pub location: String,
+    pub range: SpanRange,
+}
+
+impl<Id: Display> std::fmt::Display for TraceEvent<Id> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "event={} id={} kind={} depth={} span={} end_line={}",
+            self.event, self.id, self.kind, self.depth, self.location, self.range.end_line,
+        )
+    }
+}
+
+/// Renders trace events as an indented tree with one line per pushed context.
+///
+/// Indentation follows the actual context stack, while the bracketed number is the counted
+/// nesting depth.
+pub fn render_context_tree<Id: Display>(events: &[TraceEvent<Id>]) -> String {
+    let mut tree = String::new();
+    let mut level = 0usize;
+    for event in events {
+        if event.event == TraceEventKind::Pop {
+            level = level.saturating_sub(1);
+            continue;
+        }
+        level += 1;
+        let indent = "  ".repeat(level - 1);
+        let _ = writeln!(
+            tree,
+            "{indent}{kind} #{id} [{depth}] {location}",
+            kind = event.kind,
+            id = event.id,
+            depth = event.depth,
+            location = event.location,
+        );
+    }
+    tree
+}
+
 impl NestingDepth {
     pub fn debug_visit(&self, cx: &EarlyContext<'_>, method: &str, span: Span) {
-        if !self.config.debug {
+        if !self.stack.config.debug {
             return;
         }
 
@@ -37,7 +109,7 @@ impl NestingDepth {
     }
 
     pub fn debug_visit_extra(&self, cx: &EarlyContext<'_>, method: &str, span: Span, extra: &str) {
-        if !self.config.debug {
+        if !self.stack.config.debug {
             return;
         }
 
@@ -52,32 +124,27 @@ impl NestingDepth {
         code: bool,
         extra: Option<&str>,
     ) {
-        // return;
-        if !self.config.debug {
+        if !self.stack.config.debug || !log_enabled!(log::Level::Trace) {
             return;
         }
-        if self
-            .config
-            .debug_span_range
-            .as_ref()
-            .is_some_and(|s| !s.intersects(&self.debug_span_info(cx, span)))
+        if !self
+            .stack
+            .debug_in_span_range(&self.debug_span_info(cx, span))
         {
             return;
         }
-        let info = self.debug_span_info(cx, span);
         let code = code.then(|| self.debug_code(cx, span));
-        let depth = self.depth();
+        let depth = self.stack.depth();
         let span = self.debug_span(cx, span);
         let extra = match extra {
             Some(extra) => format!("{extra} "),
             None => String::new(),
         };
-        let debug_str = format!(
+        trace!(
             "{}[{depth:2}] {method} {extra}{span} {}",
             "  ".repeat(depth),
             code.unwrap_or_default(),
         );
-        println!("{debug_str}");
     }
 
     pub fn debug_span_info(&self, cx: &EarlyContext<'_>, span: Span) -> SpanRange {
@@ -85,7 +152,7 @@ impl NestingDepth {
     }
 
     pub fn debug_span(&self, cx: &EarlyContext<'_>, span: Span) -> String {
-        if self.config.debug {
+        if self.stack.config.debug {
             debug_span(span, cx.sess().source_map())
         } else {
             String::new()
@@ -93,7 +160,7 @@ impl NestingDepth {
     }
 
     fn debug_code(&self, cx: &EarlyContext<'_>, span: Span) -> String {
-        if !self.config.debug {
+        if !self.stack.config.debug {
             return String::new();
         }
         cx.sess()
@@ -103,6 +170,57 @@ impl NestingDepth {
     }
 }
 
+impl<Id: Copy + PartialEq + Debug + Display> ContextStack<Id> {
+    /// Logs a context PUSH or POP inside `debug_span_range`. Events are only recorded for
+    /// [`render_context_tree`] when a range is configured, so the trace stays bounded by it.
+    pub fn debug_trace_event(
+        &mut self,
+        source_map: &SourceMap,
+        event: TraceEventKind,
+        id: Id,
+        kind: ContextKind,
+        span: Span,
+        depth: usize,
+    ) {
+        if !self.config.debug {
+            return;
+        }
+        let range = debug_span_info(span, source_map);
+        if !self.debug_in_span_range(&range) {
+            return;
+        }
+        let event = TraceEvent {
+            event,
+            id,
+            kind,
+            depth,
+            location: debug_span(span, source_map),
+            range,
+        };
+        debug!("{event}");
+        if self.config.debug_span_range.is_some() {
+            self.trace_events.push(event);
+        }
+    }
+
+    /// Logs the context tree reconstructed from the trace events recorded in `debug_span_range`.
+    pub fn debug_render_trace(&self) {
+        if !self.config.debug || self.trace_events.is_empty() {
+            return;
+        }
+        let tree = render_context_tree(&self.trace_events);
+        debug!("context tree:\n{tree}");
+    }
+
+    /// Returns `true` if no `debug_span_range` is configured or the range intersects it.
+    pub fn debug_in_span_range(&self, range: &SpanRange) -> bool {
+        self.config
+            .debug_span_range
+            .as_ref()
+            .is_none_or(|s| s.intersects(range))
+    }
+}
+
 pub fn debug_span_info(span: Span, source_map: &SourceMap) -> SpanRange {
     let location_start = source_map.span_to_location_info(span);
     let location_end = source_map.span_to_location_info(span.shrink_to_hi());
@@ -188,3 +306,75 @@ pub const fn debug_expr_kind(kind: &ExprKind) -> &'static str {
         ExprKind::Dummy => "Dummy",
     }
 }
+
+/// Source of the items traced by `render_context_tree_in_span_range`
+#[cfg(test)]
+const TRACED_SOURCE: &str = "fn outside() {}
+fn inside(v: i32) {
+    if v > 0 {
+        match v {
+            _ => {}
+        }
+    }
+}
+";
+
+/// Pushes and pops the contexts of `TRACED_SOURCE` in the order the early pass does, so that
+/// the depths in the trace are the ones the stack counts.
+#[test]
+fn render_context_tree_in_span_range() {
+    use crate::context::Context;
+    use rustc_span::{BytePos, FileName, Pos, source_map::FilePathMapping};
+
+    rustc_span::create_default_session_globals_then(|| {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(
+            FileName::Custom("traced.rs".to_string()),
+            TRACED_SOURCE.to_string(),
+        );
+        let span_of = |start: &str, end: &str| {
+            let offset = TRACED_SOURCE.find(start).expect("start in TRACED_SOURCE");
+            let len = TRACED_SOURCE[offset..].find(end).expect("end in TRACED_SOURCE") + end.len();
+            let lo = file.start_pos + BytePos::from_usize(offset);
+            Span::with_root_ctxt(lo, lo + BytePos::from_usize(len))
+        };
+        let mut stack = ContextStack::new(crate::config::Config {
+            debug: true,
+            debug_span_range: Some(SpanRange {
+                file: "<traced.rs>".to_string(),
+                start_line: 2,
+                end_line: 8,
+            }),
+            ..Default::default()
+        });
+
+        let outside = Context::new(ContextKind::Func, 0, span_of("fn outside", "}"));
+        stack.push_traced(&source_map, outside);
+        stack.pop_traced(&source_map);
+
+        let inside = span_of("fn inside", "\n}");
+        let if_span = span_of("if v", "\n    }");
+        let contexts = [
+            (ContextKind::Func, inside),
+            (ContextKind::If, if_span),
+            (ContextKind::Then, if_span),
+            (ContextKind::Match, span_of("match v", "\n        }")),
+            (ContextKind::Arm, span_of("_ =>", "}")),
+        ];
+        for (id, (kind, span)) in (1..).zip(contexts) {
+            stack.push_traced(&source_map, Context::new(kind, id, span));
+        }
+        for _ in contexts {
+            stack.pop_traced(&source_map);
+        }
+
+        assert_eq!(
+            render_context_tree(&stack.trace_events),
+            "func #1 [0] <traced.rs>:2:1\n  \
+             if #2 [0] <traced.rs>:3:5\n    \
+             then #3 [1] <traced.rs>:3:5\n      \
+             match #4 [2] <traced.rs>:4:9\n        \
+             arm #5 [2] <traced.rs>:5:13\n",
+        );
+    });
+}
@@ -0,0 +1,462 @@
+use rustc_hir::{
+    Arm, Block, Body, ClosureKind, CoroutineKind, CoroutineSource, Expr, ExprKind, HirId, Item,
+    ItemKind, LoopSource, MatchSource, Node, def::DefKind,
+};
+use rustc_lint::{LateContext, LateLintPass, Level, LintContext};
+use rustc_span::Span;
+use std::collections::HashMap;
+
+use crate::{
+    EXPR_NESTING_DEPTH, NESTING_DEPTH,
+    context::{Context, ContextKind, is_iterator_method},
+    emit_lints, lints_allowed, load_config,
+    stack::ContextStack,
+    suggestion::if_chain_match_suggestion,
+};
+
+/// Late (HIR) pass variant of the lint, selected with `pass = "late"`.
+///
+/// Desugared constructs are recognized by their source instead of by their shape: `?`,
+/// `.await` and `for` matches are skipped, `while`/`for` bodies count once as their loop,
+/// and async fn bodies are not mistaken for async blocks.
+pub struct LateNestingDepth {
+    stack: ContextStack<HirId>,
+    /// Number of contexts on the stack with each id, so that a post callback finds out whether
+    /// its node pushed one without searching the stack
+    pushed_ids: HashMap<HirId, usize>,
+}
+
+impl Default for LateNestingDepth {
+    fn default() -> Self {
+        Self {
+            stack: ContextStack::new(load_config()),
+            pushed_ids: HashMap::new(),
+        }
+    }
+}
+
+rustc_session::impl_lint_pass!(LateNestingDepth => [NESTING_DEPTH, EXPR_NESTING_DEPTH]);
+
+/// Position of an expression in its parent `if`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IfBranch {
+    Then,
+    Else,
+}
+
+impl LateNestingDepth {
+    /// Returns `true` if the node is not from an external or ignored macro and can be checked.
+    fn should_check(&mut self, cx: &LateContext<'_>, span: Span) -> bool {
+        if lints_allowed(cx) {
+            return false;
+        }
+        if self.stack.span_in_ignored_macro(span) {
+            return false;
+        }
+        !span.in_external_macro(cx.sess().source_map())
+    }
+
+    fn push_context(&mut self, cx: &LateContext<'_>, kind: ContextKind, id: HirId, span: Span) {
+        self.push_context_with(cx, Context::new(kind, id, span));
+    }
+
+    fn push_context_with(&mut self, cx: &LateContext<'_>, ctx: Context<HirId>) {
+        *self.pushed_ids.entry(ctx.id).or_default() += 1;
+        self.stack.push_traced(cx.sess().source_map(), ctx);
+    }
+
+    fn pop_context(&mut self, cx: &LateContext<'_>) -> HirId {
+        let id = self.stack.pop_traced(cx.sess().source_map()).id;
+        if let Some(count) = self.pushed_ids.get_mut(&id) {
+            *count -= 1;
+            if *count == 0 {
+                self.pushed_ids.remove(&id);
+            }
+        }
+        id
+    }
+
+    /// Pops contexts up to and including the one with `id`, if it is on the stack. This also
+    /// pops the branch contexts of an `if` and the last arm context of a `match`.
+    fn pop_to(&mut self, cx: &LateContext<'_>, id: HirId) {
+        if !self.pushed_ids.contains_key(&id) {
+            return;
+        }
+        while !self.stack.contexts.is_empty() {
+            if self.pop_context(cx) == id {
+                break;
+            }
+        }
+    }
+
+    /// Emits the collected lints and drops the debug trace once no function, const or static
+    /// body is being visited anymore.
+    fn finish_item(&mut self, cx: &LateContext<'_>) {
+        if self
+            .stack
+            .contexts
+            .iter()
+            .any(|ctx| ctx.kind.is_item_root())
+        {
+            return;
+        }
+        emit_lints(cx, &mut self.stack);
+        self.stack.finish_item();
+    }
+
+    /// Enters the expression layer of a call, method call, struct, array or tuple expression.
+    fn enter_expr_layer(&mut self, expr: &Expr<'_>) {
+        match expr.kind {
+            ExprKind::MethodCall(_, receiver, ..) => {
+                self.stack
+                    .push_expr_layer(expr.hir_id, expr.span, Some(receiver.hir_id));
+            }
+            ExprKind::Call(..)
+            | ExprKind::Struct(..)
+            | ExprKind::Array(..)
+            | ExprKind::Repeat(..)
+            | ExprKind::Tup(..) => self.stack.push_expr_layer(expr.hir_id, expr.span, None),
+            _ => {}
+        }
+    }
+
+    /// Pops the arm context of the previous sibling arm, which has no post callback.
+    fn pop_arm(&mut self, cx: &LateContext<'_>) {
+        if self
+            .stack
+            .contexts
+            .last()
+            .is_some_and(|ctx| ctx.kind == ContextKind::Arm)
+        {
+            self.pop_context(cx);
+        }
+    }
+
+    /// Context kind of a body owner, or `None` for bodies that do not start a context.
+    fn body_kind(cx: &LateContext<'_>, body: &Body<'_>) -> Option<ContextKind> {
+        let owner = cx.tcx.hir_body_owner_def_id(body.id());
+        match cx.tcx.def_kind(owner) {
+            DefKind::Fn | DefKind::AssocFn => Some(ContextKind::Func),
+            DefKind::Const | DefKind::AssocConst => Some(ContextKind::Const),
+            DefKind::Static { .. } => Some(ContextKind::Static),
+            DefKind::InlineConst => Some(ContextKind::ConstBlock),
+            DefKind::Closure => closure_kind(cx.tcx.hir_node_by_def_id(owner)),
+            _ => None,
+        }
+    }
+}
+
+/// Context kind of a closure body: async blocks are told apart from closures, while the
+/// coroutines that async fns and async closures desugar into do not count on their own.
+fn closure_kind(node: Node<'_>) -> Option<ContextKind> {
+    let Node::Expr(Expr {
+        kind: ExprKind::Closure(closure),
+        ..
+    }) = node
+    else {
+        return None;
+    };
+    match closure.kind {
+        ClosureKind::Closure | ClosureKind::Coroutine(CoroutineKind::Coroutine(_)) => {
+            Some(ContextKind::Closure)
+        }
+        ClosureKind::Coroutine(CoroutineKind::Desugared(_, CoroutineSource::Block)) => {
+            Some(ContextKind::AsyncBlock)
+        }
+        ClosureKind::Coroutine(CoroutineKind::Desugared(_, CoroutineSource::Closure)) => {
+            Some(ContextKind::Closure)
+        }
+        ClosureKind::Coroutine(CoroutineKind::Desugared(_, CoroutineSource::Fn))
+        | ClosureKind::CoroutineClosure(_) => None,
+    }
+}
+
+/// The user-written block of a body, looking through the block and `DropTemps` that async
+/// bodies are wrapped in.
+fn body_block<'tcx>(body: &Body<'tcx>) -> &'tcx Expr<'tcx> {
+    let ExprKind::Block(
+        Block {
+            expr: Some(inner), ..
+        },
+        _,
+    ) = body.value.kind
+    else {
+        return body.value;
+    };
+    if body.value.span.desugaring_kind().is_none() {
+        return body.value;
+    }
+    match inner.kind {
+        ExprKind::DropTemps(user_block) => user_block,
+        _ => inner,
+    }
+}
+
+/// Returns `true` for a block with at least one statement or a trailing expression.
+fn has_items(expr: &Expr<'_>) -> bool {
+    matches!(expr.kind, ExprKind::Block(block, _) if !block.stmts.is_empty() || block.expr.is_some())
+}
+
+/// Returns `true` for the coroutine an async fn desugars into, whose block is the fn body.
+fn is_async_fn_body(cx: &LateContext<'_>, body: &Body<'_>) -> bool {
+    let owner = cx.tcx.hir_body_owner_def_id(body.id());
+    if cx.tcx.def_kind(owner) != DefKind::Closure {
+        return false;
+    }
+    matches!(
+        cx.tcx.hir_node_by_def_id(owner),
+        Node::Expr(Expr {
+            kind: ExprKind::Closure(closure),
+            ..
+        }) if matches!(closure.kind, ClosureKind::Coroutine(kind) if kind.is_fn_like())
+    )
+}
+
+/// Returns `true` for a closure passed directly to an iterator adaptor.
+fn is_adaptor_argument(cx: &LateContext<'_>, closure: HirId) -> bool {
+    parent_expr(cx, closure).is_some_and(|parent| {
+        matches!(
+            parent.kind,
+            ExprKind::MethodCall(segment, _, args, _)
+                if is_iterator_method(segment.ident.name.as_str())
+                    && args.iter().any(|arg| arg.hir_id == closure)
+        )
+    })
+}
+
+/// Returns `true` for matches written in the source rather than desugared from `?`, `for` or
+/// `.await`.
+fn is_source_match(source: MatchSource) -> bool {
+    matches!(source, MatchSource::Normal | MatchSource::Postfix)
+}
+
+fn parent_expr<'tcx>(cx: &LateContext<'tcx>, id: HirId) -> Option<&'tcx Expr<'tcx>> {
+    match cx.tcx.parent_hir_node(id) {
+        Node::Expr(expr) => Some(expr),
+        _ => None,
+    }
+}
+
+/// Returns `true` for the `if` that a `while` loop desugars into.
+fn is_while_if(cx: &LateContext<'_>, expr: &Expr<'_>) -> bool {
+    let Node::Block(block) = cx.tcx.parent_hir_node(expr.hir_id) else {
+        return false;
+    };
+    parent_expr(cx, block.hir_id)
+        .is_some_and(|parent| matches!(parent.kind, ExprKind::Loop(_, _, LoopSource::While, _)))
+}
+
+/// Returns `true` for the body of a `while` or `for` loop, which counts as the loop itself.
+fn is_loop_body(cx: &LateContext<'_>, expr: &Expr<'_>) -> bool {
+    match cx.tcx.parent_hir_node(expr.hir_id) {
+        Node::Expr(parent) => is_while_if(cx, parent),
+        Node::Arm(arm) => parent_expr(cx, arm.hir_id).is_some_and(|parent| {
+            matches!(
+                parent.kind,
+                ExprKind::Match(_, _, MatchSource::ForLoopDesugar)
+            )
+        }),
+        _ => false,
+    }
+}
+
+/// Position of the expression in its parent `if`, if it is that `if`'s then block or else
+/// branch.
+fn if_branch(cx: &LateContext<'_>, expr: &Expr<'_>) -> Option<IfBranch> {
+    let parent = parent_expr(cx, expr.hir_id)?;
+    let ExprKind::If(_, then, else_expr) = parent.kind else {
+        return None;
+    };
+    if then.hir_id == expr.hir_id {
+        return Some(IfBranch::Then);
+    }
+    else_expr
+        .is_some_and(|else_expr| else_expr.hir_id == expr.hir_id)
+        .then_some(IfBranch::Else)
+}
+
+/// Number of items (statements + expr) in the body of a match arm.
+fn arm_items(arm: &Arm<'_>) -> usize {
+    match arm.body.kind {
+        ExprKind::Block(block, _) => block.stmts.len() + usize::from(block.expr.is_some()),
+        _ => 1,
+    }
+}
+
+impl<'tcx> LateLintPass<'tcx> for LateNestingDepth {
+    fn check_crate_post(&mut self, cx: &LateContext<'tcx>) {
+        emit_lints(cx, &mut self.stack);
+    }
+
+    fn check_item(&mut self, cx: &LateContext<'tcx>, item: &'tcx Item<'tcx>) {
+        let kind = match item.kind {
+            ItemKind::Mod(_, module) if item.span.contains(module.spans.inner_span) => {
+                ContextKind::Mod
+            }
+            ItemKind::Trait(..) => ContextKind::Trait,
+            ItemKind::Impl(..) => ContextKind::Impl,
+            _ => return,
+        };
+        if !self.should_check(cx, item.span) {
+            return;
+        }
+
+        let mut ctx = Context::new(kind, item.hir_id(), item.span);
+        ctx.ident = item.kind.ident();
+        self.push_context_with(cx, ctx);
+    }
+
+    fn check_item_post(&mut self, cx: &LateContext<'tcx>, item: &'tcx Item<'tcx>) {
+        self.pop_to(cx, item.hir_id());
+        self.finish_item(cx);
+    }
+
+    fn check_body(&mut self, cx: &LateContext<'tcx>, body: &Body<'tcx>) {
+        if !self.should_check(cx, body.value.span) {
+            return;
+        }
+
+        let block = body_block(body);
+        if is_async_fn_body(cx, body) {
+            if let Some(ctx) = self.stack.contexts.last_mut()
+                && ctx.kind == ContextKind::Func
+            {
+                ctx.body_id = Some(block.hir_id);
+            }
+            return;
+        }
+        let Some(kind) = Self::body_kind(cx, body) else {
+            return;
+        };
+        let owner = cx.tcx.hir_body_owner_def_id(body.id());
+        if kind.is_closure() {
+            let iterator_adaptor = kind == ContextKind::Closure
+                && is_adaptor_argument(cx, cx.tcx.local_def_id_to_hir_id(owner));
+            self.stack
+                .push_closure(body.value.hir_id, kind, block.span, iterator_adaptor);
+        }
+        if kind.is_closure() && !has_items(block) {
+            return;
+        }
+
+        let (span, ident) = if kind.is_item_root() {
+            let hir_id = cx.tcx.local_def_id_to_hir_id(owner);
+            let ident = cx.tcx.hir_node_by_def_id(owner).ident();
+            (cx.tcx.hir_span_with_body(hir_id), ident)
+        } else {
+            (block.span, None)
+        };
+        let mut ctx = Context::new(kind, body.value.hir_id, span);
+        ctx.ident = ident;
+        ctx.body_id = Some(block.hir_id);
+        self.push_context_with(cx, ctx);
+    }
+
+    fn check_body_post(&mut self, cx: &LateContext<'tcx>, body: &Body<'tcx>) {
+        self.pop_to(cx, body.value.hir_id);
+        self.stack.pop_closure(body.value.hir_id);
+        self.finish_item(cx);
+    }
+
+    fn check_arm(&mut self, cx: &LateContext<'tcx>, arm: &'tcx Arm<'tcx>) {
+        let Some(Expr {
+            kind: ExprKind::Match(_, _, source),
+            ..
+        }) = parent_expr(cx, arm.hir_id)
+        else {
+            return;
+        };
+        if !is_source_match(*source) || !self.should_check(cx, arm.span) {
+            return;
+        }
+
+        self.pop_arm(cx);
+        if self
+            .stack
+            .contexts
+            .last()
+            .is_none_or(|ctx| ctx.kind != ContextKind::Match)
+        {
+            return;
+        }
+
+        self.push_context(cx, ContextKind::Arm, arm.hir_id, arm.pat.span);
+        self.stack.check_arm_items(arm.span, arm_items(arm));
+    }
+
+    fn check_expr(&mut self, cx: &LateContext<'tcx>, expr: &'tcx Expr<'tcx>) {
+        if !self.should_check(cx, expr.span) {
+            return;
+        }
+
+        let desugared = expr.span.desugaring_kind().is_some();
+        if !desugared {
+            self.enter_expr_layer(expr);
+        }
+        if self.stack.is_item_body(expr.hir_id) {
+            return;
+        }
+        match expr.kind {
+            ExprKind::Loop(_, _, LoopSource::ForLoop, _) => {
+                let span = expr.span.source_callsite();
+                self.push_context(cx, ContextKind::For, expr.hir_id, span);
+            }
+            _ if desugared => {}
+            ExprKind::Loop(_, _, LoopSource::While, _) => {
+                self.push_context(cx, ContextKind::While, expr.hir_id, expr.span);
+            }
+            ExprKind::Loop(_, _, LoopSource::Loop, _) => {
+                self.push_context(cx, ContextKind::Loop, expr.hir_id, expr.span);
+            }
+            ExprKind::If(_, then, else_expr) => {
+                if is_while_if(cx, expr) {
+                    return;
+                }
+                let kind = if if_branch(cx, expr) == Some(IfBranch::Else) {
+                    ContextKind::ElseIf
+                } else {
+                    ContextKind::Then
+                };
+                let then_id = match then.kind {
+                    ExprKind::Block(block, _) => block.hir_id,
+                    _ => then.hir_id,
+                };
+                let mut ctx = Context::new(ContextKind::If, expr.hir_id, expr.span);
+                if kind == ContextKind::Then
+                    && else_expr.is_some_and(|else_expr| matches!(else_expr.kind, ExprKind::If(..)))
+                {
+                    ctx.match_suggestion = if_chain_match_suggestion(cx, expr);
+                }
+                self.push_context_with(cx, ctx);
+                self.push_context(cx, kind, then_id, expr.span);
+            }
+            ExprKind::Block(..) => {
+                if is_loop_body(cx, expr) {
+                    return;
+                }
+                match if_branch(cx, expr) {
+                    Some(IfBranch::Then) => return,
+                    Some(IfBranch::Else) => {
+                        self.push_context(cx, ContextKind::Else, expr.hir_id, expr.span);
+                        return;
+                    }
+                    None => {}
+                }
+                if !has_items(expr) {
+                    return;
+                }
+                self.push_context(cx, ContextKind::ExprBlock, expr.hir_id, expr.span);
+            }
+            ExprKind::Match(_, arms, source) if is_source_match(source) => {
+                self.push_context(cx, ContextKind::Match, expr.hir_id, expr.span);
+                self.stack.check_match_arms(expr.span, arms.len());
+            }
+            _ => {}
+        }
+    }
+
+    fn check_expr_post(&mut self, cx: &LateContext<'tcx>, expr: &'tcx Expr<'tcx>) {
+        self.pop_to(cx, expr.hir_id);
+        self.stack.pop_expr_layer(expr.hir_id);
+    }
+}
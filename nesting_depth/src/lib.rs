@@ -5,59 +5,113 @@
 mod config;
 mod context;
 mod debug;
+mod late;
+mod stack;
+mod suggestion;
 
 extern crate rustc_ast;
+extern crate rustc_errors;
+extern crate rustc_hir;
+extern crate rustc_lexer;
+extern crate rustc_lint;
+extern crate rustc_session;
 extern crate rustc_span;
 
+#[cfg(not(feature = "rlib"))]
+dylint_linting::dylint_library!();
+
 const DESCRIPTION: &str = "excessive nesting";
 
+const EXPR_DESCRIPTION: &str = "excessive expression nesting";
+
+/// Label for the name of a nested item whose body starts a fresh depth count.
+const ITEM_ROOT_LABEL: &str = "depth counted from nested item";
+
+/// Label for the pattern of the match arm a linted context is nested in.
+const ARM_LABEL: &str = "in this match arm";
+
+/// Prefix of the note listing each counted context from outermost to deepest.
+const NESTING_PATH_NOTE: &str = "nesting path";
+
 use anyhow::bail;
-use config::{Config, HELP_MESSAGE};
-use context::{Context, ContextKind, NestingLint, Reason};
+use config::{Config, MATCH_SUGGESTION_MESSAGE, Pass};
+use context::{Context, ContextKind, NestingLint, is_iterator_method};
 use debug::debug_expr_kind;
 use dylint_linting::config_or_default;
-use rustc_ast::{Arm, AssocItem, Crate, Expr, ExprKind, Inline, Item, ItemKind, ModKind, NodeId};
-use rustc_lint::{EarlyContext, EarlyLintPass, Level, LintContext};
-use rustc_span::{ExpnKind, Span};
+use late::LateNestingDepth;
+use rustc_ast::{
+    Arm, AssocItem, AssocItemKind, Crate, Expr, ExprKind, Inline, Item, ItemKind, ModKind, NodeId,
+};
+use rustc_errors::Applicability;
+use rustc_lint::{EarlyContext, EarlyLintPass, Level, LintContext, LintStore};
+use rustc_session::{Session, declare_lint, impl_lint_pass};
+use rustc_span::Span;
+use stack::ContextStack;
 use std::collections::HashSet;
+use suggestion::if_chain_match_suggestion;
 
 /// Lint for detecting nesting that is too deep
 pub struct NestingDepth {
-    config: Config,
-    contexts: Vec<Context>,
-    lints: Vec<NestingLint>,
+    stack: ContextStack<NodeId>,
+    /// Node bookkeeping for the top-level item being visited.
+    item: ItemState,
+}
+
+impl Default for NestingDepth {
+    fn default() -> Self {
+        Self {
+            stack: ContextStack::new(load_config()),
+            item: ItemState::default(),
+        }
+    }
+}
+
+/// Node ids recorded while visiting a top-level item. Cleared once the item is done, so memory
+/// stays bounded by the largest item instead of growing with the crate.
+#[derive(Default)]
+struct ItemState {
     skipped_macro_ids: HashSet<NodeId>,
     checked_ids: HashSet<NodeId>,
-    /// Call site spans (macro invocation spans) for ignored macros. Any node whose span
-    /// is fully contained inside one of these will be skipped, even if its span is not
-    /// marked as coming from an expansion (e.g. tokens originating from macro input).
-    ignored_macro_call_sites: Vec<Span>,
     else_if_expr_ids: HashSet<NodeId>,
     else_block_expr_ids: HashSet<NodeId>,
-    current_nesting_lint: Option<NestingLint>,
     closure_ids: HashSet<NodeId>,
-    inside_fn: bool,
+    /// Bodies of closures passed directly to an iterator adaptor.
+    adaptor_closure_ids: HashSet<NodeId>,
 }
 
-impl Default for NestingDepth {
-    fn default() -> Self {
-        Self {
-            config: config_or_default(env!("CARGO_PKG_NAME")),
-            contexts: vec![],
-            lints: vec![],
-            skipped_macro_ids: HashSet::new(),
-            checked_ids: HashSet::new(),
-            ignored_macro_call_sites: vec![],
-            else_if_expr_ids: HashSet::new(),
-            else_block_expr_ids: HashSet::new(),
-            closure_ids: HashSet::new(),
-            current_nesting_lint: None,
-            inside_fn: false,
-        }
+impl ItemState {
+    fn clear(&mut self) {
+        self.skipped_macro_ids.clear();
+        self.checked_ids.clear();
+        self.else_if_expr_ids.clear();
+        self.else_block_expr_ids.clear();
+        self.closure_ids.clear();
+        self.adaptor_closure_ids.clear();
+    }
+}
+
+/// Reads the lint configuration, setting up logging when `debug` is enabled.
+fn load_config() -> Config {
+    let config: Config = config_or_default(env!("CARGO_PKG_NAME"));
+    if config.debug {
+        let _ = env_logger::try_init();
+    }
+    config
+}
+
+/// Registers the lint with the early (AST) or late (HIR) pass, as selected by `pass`.
+#[allow(clippy::no_mangle_with_rust_abi)]
+#[cfg_attr(not(feature = "rlib"), unsafe(no_mangle))]
+pub fn register_lints(sess: &Session, lint_store: &mut LintStore) {
+    dylint_linting::init_config(sess);
+    lint_store.register_lints(&[NESTING_DEPTH, EXPR_NESTING_DEPTH]);
+    match load_config().pass {
+        Pass::Early => lint_store.register_early_pass(|| Box::new(NestingDepth::default())),
+        Pass::Late => lint_store.register_late_pass(|_| Box::new(LateNestingDepth::default())),
     }
 }
 
-dylint_linting::impl_early_lint! {
+declare_lint! {
     /// ### What it does
     /// Checks for nested if-then-else statements and other branching that is too many levels deep.
     ///
@@ -103,148 +157,74 @@ dylint_linting::impl_early_lint! {
     /// ```
     pub NESTING_DEPTH,
     Warn,
-    DESCRIPTION,
-    NestingDepth::default()
+    DESCRIPTION
 }
 
-impl NestingDepth {
-    /// Returns true if the span (or any of its parent expansions) originates from one of the
-    /// configured ignored macros. Matching is performed against the macro's local expansion
-    /// name (identifier as written at call site after any `use as` rename).
-    fn span_in_ignored_macro(&mut self, span: Span) -> bool {
-        if self.config.ignore_macros.is_empty() {
-            return false;
-        }
-        // Quick path: if span not from expansion, it still might be inside a macro invocation's
-        // call site span (macro input tokens). Check containment first.
-        if self.span_within_ignored_callsite(span) {
-            return true;
-        }
-        if !span.from_expansion() {
-            return false;
-        }
-        // Walk outward via successive call_site spans until we leave expansion chain.
-        let mut cur = span;
-        while cur.from_expansion() {
-            let data = cur.ctxt().outer_expn_data();
-            if let ExpnKind::Macro(_, name) = data.kind {
-                let macro_name = name.as_str();
-                if self.config.ignore_macros.iter().any(|m| m == macro_name) {
-                    // Record call site span (invocation) so that any non-expansion spans inside
-                    // the macro input are also skipped later.
-                    let call_site = data.call_site;
-                    if !self
-                        .ignored_macro_call_sites
-                        .iter()
-                        .any(|s| s.lo() == call_site.lo() && s.hi() == call_site.hi())
-                    {
-                        self.ignored_macro_call_sites.push(call_site);
-                    }
-                    return true;
-                }
-            }
-            let call_site = data.call_site;
-            if call_site == cur || !call_site.from_expansion() {
-                break;
-            }
-            cur = call_site;
-        }
-        false
-    }
-
-    fn span_within_ignored_callsite(&self, span: Span) -> bool {
-        self.ignored_macro_call_sites.iter().any(|site| {
-            // simple containment check on byte positions
-            span.lo() >= site.lo() && span.hi() <= site.hi()
-        })
-    }
-    fn depth(&self) -> usize {
-        self.contexts
-            .iter()
-            .skip(1)
-            .filter(|c| c.kind.count_depth(&self.config))
-            .count()
-    }
+declare_lint! {
+    /// ### What it does
+    /// Checks for call, method call, struct, array and tuple expressions nested too many
+    /// levels deep within each other. A method call's receiver continues the call's level, so
+    /// method chains do not count as nesting.
+    ///
+    /// ### Why is this bad?
+    /// Deeply nested expressions have to be read inside out, and hide the intermediate values
+    /// they compute.
+    ///
+    /// ### Example
+    /// ```rust,no_run
+    /// # use std::collections::HashMap;
+    /// # fn foo(x: i32) {}
+    /// # fn bar(x: i32) -> i32 { x }
+    /// # fn baz(x: i32) -> i32 { x }
+    /// # fn qux(x: Vec<&i32>) -> i32 { 0 }
+    /// # let map = HashMap::from([(1, 2)]);
+    /// # let k = 1;
+    /// foo(bar(baz(qux(vec![map.get(&k).unwrap()]))));
+    /// ```
+    ///
+    /// Use instead:
+    /// ```rust,no_run
+    /// # use std::collections::HashMap;
+    /// # fn foo(x: i32) {}
+    /// # fn bar(x: i32) -> i32 { x }
+    /// # fn baz(x: i32) -> i32 { x }
+    /// # fn qux(x: Vec<&i32>) -> i32 { 0 }
+    /// # let map = HashMap::from([(1, 2)]);
+    /// # let k = 1;
+    /// let value = map.get(&k).unwrap();
+    /// let total = qux(vec![value]);
+    /// foo(bar(baz(total)));
+    /// ```
+    pub EXPR_NESTING_DEPTH,
+    Warn,
+    EXPR_DESCRIPTION
+}
 
-    fn push_context(&mut self, cx: &EarlyContext<'_>, kind: ContextKind, id: NodeId, span: Span) {
-        let ctx = Context::new(kind.clone(), id, span);
-        self.contexts.push(ctx);
-        self.debug_visit(cx, &format!("PUSH CONTEXT: {id} {kind}"), span);
+impl_lint_pass!(NestingDepth => [NESTING_DEPTH, EXPR_NESTING_DEPTH]);
 
-        let depth = self.depth();
-        if depth <= self.config.max_depth {
-            return;
+impl NestingDepth {
+    /// Pops the arm context of the previous sibling arm, which has no post callback.
+    fn pop_arm(&mut self, cx: &EarlyContext<'_>) {
+        if self
+            .stack
+            .contexts
+            .last()
+            .is_some_and(|ctx| ctx.kind == ContextKind::Arm)
+        {
+            self.pop_context_unchecked(cx);
         }
-
-        let outer_span = self.contexts.get(1).map(|ctx| ctx.span);
-
-        let lint = self.current_nesting_lint.get_or_insert(NestingLint {
-            outer_span,
-            span,
-            kind,
-            reason: Reason::Depth(depth),
-        });
-        lint.reason = Reason::Depth(depth);
     }
 
-    fn push_current_lints(&mut self, cx: &EarlyContext<'_>, ctx: &mut Context) {
-        if let Some(lint) = self.current_nesting_lint.take() {
-            self.lints.push(lint);
-        }
-
-        if ctx.consec_if_branch_count > self.config.max_consec_if_else {
-            let outer_span = self.contexts.get(1).map(|ctx| ctx.span);
-            self.lints.push(NestingLint {
-                outer_span,
-                span: ctx.span,
-                kind: ContextKind::If,
-                reason: Reason::ConsecIfElse(ctx.consec_if_branch_count),
-            });
-        }
+    fn push_context(&mut self, cx: &EarlyContext<'_>, kind: ContextKind, id: NodeId, span: Span) {
+        self.push_context_with(cx, Context::new(kind, id, span));
     }
 
-    fn find_root_if_parent(&mut self) -> Option<&mut Context> {
-        let mut iter = self.contexts.iter_mut().rev();
-        iter.reduce(|mut acc, ctx| {
-            if ctx.kind.is_if_or_if_branch() {
-                acc = ctx;
-            }
-            acc
-        })
+    fn push_context_with(&mut self, cx: &EarlyContext<'_>, ctx: Context) {
+        self.stack.push_traced(cx.sess().source_map(), ctx);
     }
 
     fn pop_context_unchecked(&mut self, cx: &EarlyContext<'_>) -> Context {
-        let mut ctx = self.contexts.pop().expect("pop context unchecked");
-
-        self.debug_visit(
-            cx,
-            &format!("POP CONTEXT: {} {}", ctx.id, ctx.kind),
-            ctx.span,
-        );
-
-        if ctx.kind.is_if_branch() {
-            if let Some(if_parent) = self.find_root_if_parent() {
-                match ctx.kind {
-                    ContextKind::If => {
-                        // if_parent.consec_if_else_count += 1;
-                    }
-                    ContextKind::Then | ContextKind::ElseIf | ContextKind::Else => {
-                        // These can only exist within a ContextKind::If,
-                        // and are automatically "reset" when the current If is popped.
-                        if_parent.consec_if_branch_count += 1;
-                    }
-                    _ => {
-                        // if_parent.consec_if_else_count = 0;
-                    }
-                }
-            } else {
-                panic!("DID NOT FIND IF PARENT: {} {}", ctx.kind, ctx.id);
-            }
-        }
-
-        self.push_current_lints(cx, &mut ctx);
-
-        ctx
+        self.stack.pop_traced(cx.sess().source_map())
     }
 
     fn pop_context(&mut self, cx: &EarlyContext<'_>, id: &NodeId) -> Result<(), anyhow::Error> {
@@ -263,36 +243,38 @@ impl NestingDepth {
 
     /// Returns `true` if the node is not from a macro expansion and can be checked
     fn should_check_id(&mut self, cx: &EarlyContext<'_>, id: NodeId, span: Span) -> bool {
-        if cx.get_lint_level(NESTING_DEPTH).level == Level::Allow {
+        if lints_allowed(cx) {
             return false;
         }
-        if self.checked_ids.contains(&id) {
+        if self.item.checked_ids.contains(&id) {
             return true;
         }
-        if self.skipped_macro_ids.contains(&id) {
+        if self.item.skipped_macro_ids.contains(&id) {
             return false;
         }
         // Ignore nodes whose spans originate from an ignored macro expansion.
-        if self.span_in_ignored_macro(span) {
-            self.skipped_macro_ids.insert(id);
+        if self.stack.span_in_ignored_macro(span) {
+            self.item.skipped_macro_ids.insert(id);
             return false;
         }
         // Also skip if span lies within any previously recorded ignored macro call site.
-        if self.span_within_ignored_callsite(span) {
-            self.skipped_macro_ids.insert(id);
+        if self.stack.span_within_ignored_callsite(span) {
+            self.item.skipped_macro_ids.insert(id);
             return false;
         }
         if span.ctxt().in_external_macro(cx.sess().source_map()) {
-            self.skipped_macro_ids.insert(id);
+            self.item.skipped_macro_ids.insert(id);
             return false;
         }
-        self.checked_ids.insert(id);
+        self.item.checked_ids.insert(id);
         true
     }
 
     fn item_kind(&mut self, cx: &EarlyContext<'_>, item: &Item) -> Option<ContextKind> {
         match &item.kind {
             ItemKind::Fn(_) => Some(ContextKind::Func),
+            ItemKind::Const(_) => Some(ContextKind::Const),
+            ItemKind::Static(_) => Some(ContextKind::Static),
             ItemKind::Mod(_, _, ModKind::Loaded(_, Inline::Yes, _)) => Some(ContextKind::Mod),
             ItemKind::Trait(_) => Some(ContextKind::Trait),
             ItemKind::Impl(_) => Some(ContextKind::Impl),
@@ -300,20 +282,161 @@ impl NestingDepth {
         }
         .filter(|_| self.should_check_id(cx, item.id, item.span))
     }
+
+    fn assoc_item_kind(&mut self, cx: &EarlyContext<'_>, item: &AssocItem) -> Option<ContextKind> {
+        match &item.kind {
+            AssocItemKind::Fn(func) if func.body.is_some() => Some(ContextKind::Func),
+            AssocItemKind::Const(item) if item.rhs.is_some() => Some(ContextKind::Const),
+            _ => None,
+        }
+        .filter(|_| self.should_check_id(cx, item.id, item.span))
+    }
+
+    fn enter_assoc_item(&mut self, cx: &EarlyContext<'_>, item: &AssocItem) {
+        let Some(kind) = self.assoc_item_kind(cx, item) else {
+            return;
+        };
+
+        let mut ctx = Context::new(kind, item.id, item.span);
+        ctx.ident = item.kind.ident();
+        ctx.body_id = match &item.kind {
+            AssocItemKind::Const(item) => item.rhs.as_ref().map(|rhs| rhs.expr().id),
+            _ => None,
+        };
+        self.push_context_with(cx, ctx);
+        self.debug_visit(cx, "ENTER assoc item", item.span);
+    }
+
+    fn exit_assoc_item(&mut self, cx: &EarlyContext<'_>, item: &AssocItem) {
+        if self.is_current_context(item.id) {
+            self.debug_visit(cx, "EXIT assoc item", item.span);
+            self.pop_context(cx, &item.id)
+                .expect("pop assoc item context");
+        }
+        self.finish_item(cx);
+    }
+
+    /// Returns `true` if the innermost context belongs to the node.
+    fn is_current_context(&self, id: NodeId) -> bool {
+        self.stack.contexts.last().is_some_and(|ctx| ctx.id == id)
+    }
+
+    /// Emits the lints of a finished item and drops its node bookkeeping and debug trace once
+    /// no function, const or static body is being visited anymore.
+    fn finish_item(&mut self, cx: &EarlyContext<'_>) {
+        if self
+            .stack
+            .contexts
+            .iter()
+            .any(|ctx| ctx.kind.is_item_root())
+        {
+            return;
+        }
+        emit_lints(cx, &mut self.stack);
+        self.stack.finish_item();
+        self.item.clear();
+    }
+
+    /// Enters the expression layer of a call, method call, struct, array or tuple expression.
+    fn enter_expr_layer(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::MethodCall(call) => {
+                self.stack
+                    .push_expr_layer(expr.id, expr.span, Some(call.receiver.id));
+            }
+            ExprKind::Call(..)
+            | ExprKind::Struct(..)
+            | ExprKind::Array(..)
+            | ExprKind::Repeat(..)
+            | ExprKind::Tup(..) => self.stack.push_expr_layer(expr.id, expr.span, None),
+            _ => {}
+        }
+    }
+
+    /// Returns `true` if the block expression is the initializer of the enclosing const or
+    /// static, which is treated as the item body rather than as a nested block.
+    fn is_item_body(&self, expr: &Expr) -> bool {
+        self.stack.is_item_body(expr.id)
+    }
+}
+
+/// Formats each context of a nesting path as `<level>: <kind> (line <line>)`.
+fn nesting_path(cx: &impl LintContext, path: &[(ContextKind, Span)]) -> String {
+    let source_map = cx.sess().source_map();
+    path.iter()
+        .enumerate()
+        .map(|(index, (kind, span))| {
+            let level = index + 1;
+            let line = source_map.lookup_char_pos(span.lo()).line;
+            format!("{level}: {kind} (line {line})")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Emits and clears the collected lints with their labels, nesting path note and help or
+/// suggestion.
+fn emit_lints<Id>(cx: &impl LintContext, stack: &mut ContextStack<Id>) {
+    let config = &stack.config;
+    for lint in stack.lints.drain(..) {
+        cx.span_lint(lint.reason.lint(), lint.span, |diag| {
+            if let Some(outer_span) = lint.outer_span {
+                diag.span_label(outer_span, lint.reason.outer_context_label());
+            }
+            if let Some(item) = lint.item {
+                diag.span_label(item.span, format!("{ITEM_ROOT_LABEL} `{item}`"));
+            }
+            if let Some(arm) = lint.arm {
+                diag.span_label(arm, ARM_LABEL);
+            }
+            if !lint.path.is_empty() {
+                let path = nesting_path(cx, &lint.path);
+                diag.note(format!("{NESTING_PATH_NOTE}: {path}"));
+            }
+            diag.primary_message(lint.reason.message(config));
+            let Some(suggestion) = &lint.suggestion else {
+                diag.help(lint.reason.help());
+                return;
+            };
+            diag.span_suggestion(
+                lint.span,
+                MATCH_SUGGESTION_MESSAGE,
+                suggestion,
+                Applicability::MaybeIncorrect,
+            );
+        });
+    }
+}
+
+/// Returns `true` if both lints are allowed at the current node.
+fn lints_allowed(cx: &impl LintContext) -> bool {
+    [NESTING_DEPTH, EXPR_NESTING_DEPTH]
+        .iter()
+        .all(|lint| cx.get_lint_level(lint).level == Level::Allow)
+}
+
+/// Number of items (statements + expr) in the body of a match arm.
+fn arm_items(arm: &Arm) -> usize {
+    match arm.body.as_deref().map(|body| &body.kind) {
+        Some(ExprKind::Block(block, _)) => block.stmts.len(),
+        Some(_) => 1,
+        None => 0,
+    }
+}
+
+/// Initializer expression of a const or static item.
+fn item_body_id(kind: &ItemKind) -> Option<NodeId> {
+    match kind {
+        ItemKind::Const(item) => item.rhs.as_ref().map(|rhs| rhs.expr().id),
+        ItemKind::Static(item) => item.expr.as_ref().map(|expr| expr.id),
+        _ => None,
+    }
 }
 
 impl EarlyLintPass for NestingDepth {
     #[inline(always)]
     fn check_crate_post(&mut self, cx: &EarlyContext<'_>, _krate: &Crate) {
-        for lint in &self.lints {
-            cx.span_lint(NESTING_DEPTH, lint.span, |diag| {
-                if let Some(outer_span) = lint.outer_span {
-                    diag.span_label(outer_span, lint.reason.outer_context_label());
-                }
-                diag.primary_message(lint.reason.message(&self.config));
-                diag.help(HELP_MESSAGE);
-            });
-        }
+        emit_lints(cx, &mut self.stack);
     }
 
     #[inline(always)]
@@ -322,23 +445,41 @@ impl EarlyLintPass for NestingDepth {
             return;
         };
 
-        self.push_context(cx, kind, item.id, item.span);
+        let mut ctx = Context::new(kind, item.id, item.span);
+        ctx.ident = item.kind.ident();
+        ctx.body_id = item_body_id(&item.kind);
+        self.push_context_with(cx, ctx);
         self.debug_visit_extra(cx, "ENTER item", item.span, item.kind.descr());
     }
 
     #[inline(always)]
     fn check_item_post(&mut self, cx: &EarlyContext<'_>, item: &Item) {
-        if !self.checked_ids.contains(&item.id) {
-            return;
+        if self.is_current_context(item.id) {
+            self.debug_visit_extra(cx, "EXIT item", item.span, item.kind.descr());
+            self.pop_context(cx, &item.id).expect("pop item context");
         }
-
-        self.debug_visit_extra(cx, "EXIT item", item.span, item.kind.descr());
-        self.pop_context(cx, &item.id).expect("pop item context");
+        self.finish_item(cx);
     }
 
     #[inline(always)]
     fn check_arm(&mut self, cx: &EarlyContext<'_>, arm: &Arm) {
-        // println!("CHECK ARM");
+        if !self.should_check_id(cx, arm.id, arm.span) {
+            return;
+        }
+
+        self.pop_arm(cx);
+        if self
+            .stack
+            .contexts
+            .last()
+            .is_none_or(|ctx| ctx.kind != ContextKind::Match)
+        {
+            return;
+        }
+
+        self.debug_visit(cx, &format!("ENTER ARM: {}", arm.id), arm.span);
+        self.push_context(cx, ContextKind::Arm, arm.id, arm.pat.span);
+        self.stack.check_arm_items(arm.span, arm_items(arm));
     }
 
     #[inline(always)]
@@ -348,16 +489,25 @@ impl EarlyLintPass for NestingDepth {
         }
 
         // println!("CHECK EXPR ID: {} {}", expr.id, debug_expr_kind(&expr.kind));
+        self.enter_expr_layer(expr);
 
         match &expr.kind {
             // enter the `if` or `else-if` block context
             ExprKind::If(_cond, if_or_else_if_block, else_expr) => {
-                let kind = if self.else_if_expr_ids.contains(&expr.id) {
+                let kind = if self.item.else_if_expr_ids.contains(&expr.id) {
                     ContextKind::ElseIf
                 } else {
                     ContextKind::Then
                 };
                 self.push_context(cx, ContextKind::If, expr.id, expr.span);
+                if kind == ContextKind::Then
+                    && else_expr
+                        .as_ref()
+                        .is_some_and(|else_expr| matches!(else_expr.kind, ExprKind::If(..)))
+                    && let Some(if_ctx) = self.stack.contexts.last_mut()
+                {
+                    if_ctx.match_suggestion = if_chain_match_suggestion(cx, expr);
+                }
                 self.push_context(cx, kind, if_or_else_if_block.id, expr.span);
                 self.debug_visit(
                     cx,
@@ -376,17 +526,24 @@ impl EarlyLintPass for NestingDepth {
                     );
                     match &else_expr.kind {
                         ExprKind::If(..) => {
-                            self.else_if_expr_ids.insert(else_expr.id);
+                            self.item.else_if_expr_ids.insert(else_expr.id);
                         }
                         ExprKind::Block(..) => {
-                            self.else_block_expr_ids.insert(else_expr.id);
+                            self.item.else_block_expr_ids.insert(else_expr.id);
                         }
                         _ => unreachable!("impossible else expr kind"),
                     }
                 }
             }
+            ExprKind::MethodCall(call) if is_iterator_method(call.seg.ident.name.as_str()) => {
+                for arg in &call.args {
+                    if let ExprKind::Closure(closure) = &arg.kind {
+                        self.item.adaptor_closure_ids.insert(closure.body.id);
+                    }
+                }
+            }
             ExprKind::Closure(closure) => {
-                self.closure_ids.insert(closure.body.id);
+                self.item.closure_ids.insert(closure.body.id);
                 self.debug_visit(
                     cx,
                     &format!(
@@ -396,9 +553,19 @@ impl EarlyLintPass for NestingDepth {
                     ),
                     expr.span,
                 );
+                let iterator_adaptor = self.item.adaptor_closure_ids.contains(&closure.body.id);
+                self.stack.push_closure(
+                    expr.id,
+                    ContextKind::Closure,
+                    closure.body.span,
+                    iterator_adaptor,
+                );
             }
             ExprKind::Block(block, _) => {
-                if self.else_block_expr_ids.contains(&expr.id) {
+                if self.is_item_body(expr) {
+                    return;
+                }
+                if self.item.else_block_expr_ids.contains(&expr.id) {
                     // entered `else` block context
                     // branch wrappers (ContextKind::If) are only popped in post ExprKind::If
                     // DO NOT pop context here.
@@ -413,7 +580,7 @@ impl EarlyLintPass for NestingDepth {
                 if block.stmts.is_empty() {
                     return;
                 }
-                if self.closure_ids.contains(&expr.id) {
+                if self.item.closure_ids.contains(&expr.id) {
                     self.debug_visit(
                         cx,
                         &format!("ENTER CLOSURE BLOCK: {} {}", expr.id, block.id),
@@ -429,9 +596,25 @@ impl EarlyLintPass for NestingDepth {
                 );
                 self.push_context(cx, ContextKind::ExprBlock, expr.id, expr.span);
             }
-            ExprKind::Match(..) => {
+            ExprKind::Match(_, arms, _) => {
                 self.debug_visit(cx, &format!("ENTER MATCH: {}", expr.id), expr.span);
                 self.push_context(cx, ContextKind::Match, expr.id, expr.span);
+                self.stack.check_match_arms(expr.span, arms.len());
+            }
+            ExprKind::ConstBlock(anon_const) => {
+                self.debug_visit(cx, &format!("ENTER CONST BLOCK: {}", expr.id), expr.span);
+                let mut ctx = Context::new(ContextKind::ConstBlock, expr.id, expr.span);
+                ctx.body_id = Some(anon_const.value.id);
+                self.push_context_with(cx, ctx);
+            }
+            ExprKind::Gen(_, block, ..) => {
+                self.stack
+                    .push_closure(expr.id, ContextKind::AsyncBlock, expr.span, false);
+                if block.stmts.is_empty() {
+                    return;
+                }
+                self.debug_visit(cx, &format!("ENTER ASYNC BLOCK: {}", expr.id), expr.span);
+                self.push_context(cx, ContextKind::AsyncBlock, expr.id, expr.span);
             }
 
             _ => {}
@@ -440,9 +623,10 @@ impl EarlyLintPass for NestingDepth {
 
     #[inline(always)]
     fn check_expr_post(&mut self, cx: &EarlyContext<'_>, expr: &Expr) {
-        if !self.checked_ids.contains(&expr.id) {
+        if !self.item.checked_ids.contains(&expr.id) {
             return;
         }
+        self.stack.pop_expr_layer(expr.id);
 
         match &expr.kind {
             // EXIT the `if` or `else-if` block context
@@ -453,7 +637,7 @@ impl EarlyLintPass for NestingDepth {
                     expr.span,
                 );
                 while matches!(
-                    self.contexts.last().map(|c| c.kind),
+                    self.stack.contexts.last().map(|c| c.kind),
                     Some(
                         ContextKind::If
                             | ContextKind::Then
@@ -468,7 +652,10 @@ impl EarlyLintPass for NestingDepth {
                 }
             }
             ExprKind::Block(block, _) => {
-                if self.else_block_expr_ids.contains(&expr.id) {
+                if self.is_item_body(expr) {
+                    return;
+                }
+                if self.item.else_block_expr_ids.contains(&expr.id) {
                     // EXIT the `else` block context
                     self.debug_visit(
                         cx,
@@ -491,20 +678,49 @@ impl EarlyLintPass for NestingDepth {
             }
             ExprKind::Match(..) => {
                 self.debug_visit(cx, &format!("EXIT MATCH: {}", expr.id), expr.span);
+                self.pop_arm(cx);
                 self.pop_context(cx, &expr.id).expect("pop match context");
             }
+            ExprKind::ConstBlock(..) => {
+                self.debug_visit(cx, &format!("EXIT CONST BLOCK: {}", expr.id), expr.span);
+                self.pop_context(cx, &expr.id)
+                    .expect("pop const block context");
+            }
+            ExprKind::Closure(..) => {
+                self.debug_visit(cx, &format!("EXIT CLOSURE: {}", expr.id), expr.span);
+                self.stack.pop_closure(expr.id);
+            }
+            ExprKind::Gen(_, block, ..) => {
+                self.stack.pop_closure(expr.id);
+                if block.stmts.is_empty() {
+                    return;
+                }
+                self.debug_visit(cx, &format!("EXIT ASYNC BLOCK: {}", expr.id), expr.span);
+                self.pop_context(cx, &expr.id)
+                    .expect("pop async block context");
+            }
             _ => {}
         }
     }
 
     #[inline(always)]
-    fn check_trait_item(&mut self, cx: &EarlyContext<'_>, _: &AssocItem) {
-        // println!("CHECK TRAIT ITEM");
+    fn check_trait_item(&mut self, cx: &EarlyContext<'_>, item: &AssocItem) {
+        self.enter_assoc_item(cx, item);
     }
 
     #[inline(always)]
-    fn check_trait_item_post(&mut self, cx: &EarlyContext<'_>, _: &AssocItem) {
-        // println!("CHECK TRAIT ITEM POST");
+    fn check_trait_item_post(&mut self, cx: &EarlyContext<'_>, item: &AssocItem) {
+        self.exit_assoc_item(cx, item);
+    }
+
+    #[inline(always)]
+    fn check_impl_item(&mut self, cx: &EarlyContext<'_>, item: &AssocItem) {
+        self.enter_assoc_item(cx, item);
+    }
+
+    #[inline(always)]
+    fn check_impl_item_post(&mut self, cx: &EarlyContext<'_>, item: &AssocItem) {
+        self.exit_assoc_item(cx, item);
     }
 }
 
@@ -512,3 +728,31 @@ impl EarlyLintPass for NestingDepth {
 fn ui() {
     dylint_uitesting::ui_test(env!("CARGO_PKG_NAME"), "ui");
 }
+
+#[test]
+fn ui_late() {
+    dylint_uitesting::ui::Test::src_base(env!("CARGO_PKG_NAME"), "ui_late")
+        .dylint_toml("[nesting_depth]\npass = \"late\"")
+        .run();
+}
+
+#[test]
+fn ui_report_first() {
+    dylint_uitesting::ui::Test::src_base(env!("CARGO_PKG_NAME"), "ui_report_first")
+        .dylint_toml("[nesting_depth]\nreport = \"first\"")
+        .run();
+}
+
+#[test]
+fn ui_report_deepest() {
+    dylint_uitesting::ui::Test::src_base(env!("CARGO_PKG_NAME"), "ui_report_deepest")
+        .dylint_toml("[nesting_depth]\nreport = \"deepest\"")
+        .run();
+}
+
+#[test]
+fn ui_no_iterator_adaptors() {
+    dylint_uitesting::ui::Test::src_base(env!("CARGO_PKG_NAME"), "ui_no_iterator_adaptors")
+        .dylint_toml("[nesting_depth]\nmax_closure_depth = 1\nallow_iterator_adaptors = false")
+        .run();
+}
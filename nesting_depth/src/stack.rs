@@ -0,0 +1,572 @@
+use std::{
+    fmt::{Debug, Display},
+    mem::discriminant,
+};
+
+use rustc_span::{ExpnKind, Span, source_map::SourceMap, symbol::Ident};
+
+use crate::{
+    config::{Config, ReportMode},
+    context::{ClosureFrame, Context, ContextKind, ExprLayer, NestingLint, Reason, Region},
+    debug::{TraceEvent, TraceEventKind},
+};
+
+/// Stack of nesting contexts shared by the early (AST) and late (HIR) passes, generic over the
+/// node id type of the pass. Tracks the over-budget regions and collects the resulting lints.
+pub struct ContextStack<Id> {
+    pub config: Config,
+    pub contexts: Vec<Context<Id>>,
+    pub lints: Vec<NestingLint>,
+    /// Context PUSH/POP events recorded when `debug` is enabled.
+    pub trace_events: Vec<TraceEvent<Id>>,
+    /// Call, method call, struct, array and tuple expressions being visited.
+    expr_layers: Vec<ExprLayer<Id>>,
+    /// Closures and async blocks being visited, whatever their body.
+    closures: Vec<ClosureFrame<Id>>,
+    /// Call site spans (macro invocation spans) for ignored macros. Any node whose span
+    /// is fully contained inside one of these will be skipped, even if its span is not
+    /// marked as coming from an expansion (e.g. tokens originating from macro input).
+    ignored_macro_call_sites: Vec<Span>,
+    /// Open regions of contexts deeper than `max_depth`, one per item root.
+    depth_regions: Vec<Region<Id>>,
+    /// Open regions of closures nested deeper than `max_closure_depth`, one per item root.
+    closure_regions: Vec<Region<Id>>,
+    /// Open region of items nested deeper than `max_item_nesting`, kept under a single root
+    /// since item nesting is counted over the whole stack.
+    item_regions: Vec<Region<Id>>,
+    /// Open regions of expressions nested deeper than `max_expr_depth`, one per item root.
+    expr_regions: Vec<Region<Id>>,
+}
+
+impl<Id: Copy + PartialEq + Debug> ContextStack<Id> {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            contexts: vec![],
+            lints: vec![],
+            trace_events: vec![],
+            expr_layers: vec![],
+            closures: vec![],
+            ignored_macro_call_sites: vec![],
+            depth_regions: vec![],
+            closure_regions: vec![],
+            item_regions: vec![],
+            expr_regions: vec![],
+        }
+    }
+
+    /// Returns true if the span (or any of its parent expansions) originates from one of the
+    /// configured ignored macros. Matching is performed against the macro's local expansion
+    /// name (identifier as written at call site after any `use as` rename).
+    pub fn span_in_ignored_macro(&mut self, span: Span) -> bool {
+        if self.config.ignore_macros.is_empty() {
+            return false;
+        }
+        // Quick path: if span not from expansion, it still might be inside a macro invocation's
+        // call site span (macro input tokens). Check containment first.
+        if self.span_within_ignored_callsite(span) {
+            return true;
+        }
+        if !span.from_expansion() {
+            return false;
+        }
+        // Walk outward via successive call_site spans until we leave expansion chain.
+        let mut cur = span;
+        while cur.from_expansion() {
+            let data = cur.ctxt().outer_expn_data();
+            if let ExpnKind::Macro(_, name) = data.kind {
+                let macro_name = name.as_str();
+                if self.config.ignore_macros.iter().any(|m| m == macro_name) {
+                    // Record call site span (invocation) so that any non-expansion spans inside
+                    // the macro input are also skipped later.
+                    let call_site = data.call_site;
+                    if !self
+                        .ignored_macro_call_sites
+                        .iter()
+                        .any(|s| s.lo() == call_site.lo() && s.hi() == call_site.hi())
+                    {
+                        self.ignored_macro_call_sites.push(call_site);
+                    }
+                    return true;
+                }
+            }
+            let call_site = data.call_site;
+            if call_site == cur || !call_site.from_expansion() {
+                break;
+            }
+            cur = call_site;
+        }
+        false
+    }
+
+    pub fn span_within_ignored_callsite(&self, span: Span) -> bool {
+        self.ignored_macro_call_sites.iter().any(|site| {
+            // simple containment check on byte positions
+            span.lo() >= site.lo() && span.hi() <= site.hi()
+        })
+    }
+
+    /// Index of the innermost item body (fn, const or static) on the stack, from which depth
+    /// is counted. Falls back to the bottom of the stack when there is none.
+    fn root_index(&self) -> usize {
+        self.contexts
+            .iter()
+            .rposition(|c| c.kind.is_item_root())
+            .unwrap_or(0)
+    }
+
+    /// Contexts nested inside the innermost item body.
+    fn root_contexts(&self) -> &[Context<Id>] {
+        self.contexts
+            .get(self.root_index() + 1..)
+            .unwrap_or_default()
+    }
+
+    /// Outermost context inside the innermost item body.
+    fn outer_context_span(&self) -> Option<Span> {
+        self.root_contexts().first().map(|ctx| ctx.span)
+    }
+
+    /// Name of the innermost item body, if that item is itself nested in another item's body.
+    fn nested_item(&self) -> Option<Ident> {
+        let root = self.root_index();
+        let nested = self.contexts[..root].iter().any(|c| c.kind.is_item_root());
+        nested
+            .then(|| self.contexts.get(root).and_then(|ctx| ctx.ident))
+            .flatten()
+    }
+
+    /// Kinds and spans of the contexts inside the innermost item body that match `filter`.
+    fn path(&self, filter: impl Fn(&Context<Id>) -> bool) -> Vec<(ContextKind, Span)> {
+        self.root_contexts()
+            .iter()
+            .filter(|ctx| filter(ctx))
+            .map(|ctx| (ctx.kind, ctx.span))
+            .collect()
+    }
+
+    /// Pattern span of the innermost match arm inside the innermost item body.
+    fn enclosing_arm(&self) -> Option<Span> {
+        self.root_contexts()
+            .iter()
+            .rfind(|ctx| ctx.kind == ContextKind::Arm)
+            .map(|ctx| ctx.span)
+    }
+
+    /// Reports a match with more than `max_match_arms` arms.
+    pub fn check_match_arms(&mut self, span: Span, arms: usize) {
+        if arms <= self.config.max_match_arms {
+            return;
+        }
+
+        let item = self.nested_item();
+        self.lints.push(NestingLint {
+            outer_span: None,
+            span,
+            kind: ContextKind::Match,
+            reason: Reason::MatchArms(arms),
+            item,
+            path: vec![],
+            arm: None,
+            suggestion: None,
+        });
+    }
+
+    /// Reports a match arm whose body has more than `max_arm_items` items.
+    pub fn check_arm_items(&mut self, span: Span, items: usize) {
+        if items <= self.config.max_arm_items {
+            return;
+        }
+
+        let outer_span = self
+            .contexts
+            .iter()
+            .rfind(|ctx| ctx.kind == ContextKind::Match)
+            .map(|ctx| ctx.span);
+        let item = self.nested_item();
+        self.lints.push(NestingLint {
+            outer_span,
+            span,
+            kind: ContextKind::Arm,
+            reason: Reason::ArmItems(items),
+            item,
+            path: vec![],
+            arm: None,
+            suggestion: None,
+        });
+    }
+
+    pub fn depth(&self) -> usize {
+        self.root_contexts()
+            .iter()
+            .filter(|c| c.kind.count_depth(&self.config))
+            .count()
+    }
+
+    pub fn push(&mut self, ctx: Context<Id>) {
+        let (kind, id, span, ident) = (ctx.kind, ctx.id, ctx.span, ctx.ident);
+        self.contexts.push(ctx);
+
+        self.check_item_nesting(kind, id, ident.map_or(span, |ident| ident.span));
+
+        let depth = self.depth();
+        if depth <= self.config.max_depth {
+            return;
+        }
+
+        let outer_span = self.outer_context_span();
+        let item = self.nested_item();
+        let path = self.path(|ctx| ctx.kind.count_depth(&self.config));
+        let arm = self.enclosing_arm();
+
+        let lint = NestingLint {
+            outer_span,
+            span,
+            kind,
+            reason: Reason::Depth(depth),
+            item,
+            path,
+            arm,
+            suggestion: None,
+        };
+        let root = self.root_index();
+        Region::track(&mut self.depth_regions, root, id, lint);
+    }
+
+    /// Closures inside the innermost item body.
+    fn root_closures(&self) -> impl Iterator<Item = &ClosureFrame<Id>> + Clone {
+        let root = self.root_index();
+        self.closures
+            .iter()
+            .filter(move |frame| frame.context > root)
+    }
+
+    /// Returns `true` for two closures nested within each other that are both passed to
+    /// iterator adaptors, when `allow_iterator_adaptors` is set.
+    fn is_iterator_adaptor_pair(&self) -> bool {
+        if !self.config.allow_iterator_adaptors {
+            return false;
+        }
+        let mut closures = self.root_closures();
+        matches!(
+            (closures.next(), closures.next(), closures.next()),
+            (Some(outer), Some(inner), None) if outer.iterator_adaptor && inner.iterator_adaptor
+        )
+    }
+
+    /// Enters a closure or async block, which nests one level deeper in the closures of the
+    /// innermost item body, independently of `depth`.
+    pub fn push_closure(&mut self, id: Id, kind: ContextKind, span: Span, iterator_adaptor: bool) {
+        self.closures.push(ClosureFrame {
+            id,
+            kind,
+            span,
+            context: self.contexts.len(),
+            iterator_adaptor,
+        });
+
+        let closure_depth = self.root_closures().count();
+        if closure_depth <= self.config.max_closure_depth || self.is_iterator_adaptor_pair() {
+            return;
+        }
+
+        let outer_span = self.root_closures().next().map(|frame| frame.span);
+        let item = self.nested_item();
+        let path = self
+            .root_closures()
+            .map(|frame| (frame.kind, frame.span))
+            .collect();
+        let arm = self.enclosing_arm();
+
+        let lint = NestingLint {
+            outer_span,
+            span,
+            kind,
+            reason: Reason::ClosureDepth(closure_depth),
+            item,
+            path,
+            arm,
+            suggestion: None,
+        };
+        let root = self.root_index();
+        Region::track(&mut self.closure_regions, root, id, lint);
+    }
+
+    /// Leaves the closure `id` if it is the innermost closure.
+    pub fn pop_closure(&mut self, id: Id) {
+        if self.closures.pop_if(|frame| frame.id == id).is_some() {
+            self.close_regions(id);
+        }
+    }
+
+    /// Number of items nested within each other on the whole stack, counted independently of
+    /// `depth`.
+    fn item_nesting(&self) -> usize {
+        self.contexts.iter().filter(|c| c.kind.is_item()).count()
+    }
+
+    fn check_item_nesting(&mut self, kind: ContextKind, id: Id, span: Span) {
+        if !kind.is_item() {
+            return;
+        }
+
+        let item_nesting = self.item_nesting();
+        if item_nesting <= self.config.max_item_nesting {
+            return;
+        }
+
+        let items = self.contexts.iter().filter(|ctx| ctx.kind.is_item());
+        let outer_span = items
+            .clone()
+            .next()
+            .map(|ctx| ctx.ident.map_or(ctx.span, |ident| ident.span));
+        let path = items.map(|ctx| (ctx.kind, ctx.span)).collect();
+
+        let lint = NestingLint {
+            outer_span,
+            span,
+            kind,
+            reason: Reason::ItemNesting(item_nesting),
+            item: None,
+            path,
+            arm: None,
+            suggestion: None,
+        };
+        Region::track(&mut self.item_regions, 0, id, lint);
+    }
+
+    /// Enters a call, method call, struct, array or tuple expression. It nests one level
+    /// deeper than the enclosing layer of the same context, unless it is that layer's method
+    /// call receiver.
+    pub fn push_expr_layer(&mut self, id: Id, span: Span, receiver: Option<Id>) {
+        let context = self.contexts.len();
+        let depth = match self.expr_layers.last() {
+            Some(parent) if parent.context == context && parent.receiver == Some(id) => {
+                parent.depth
+            }
+            Some(parent) if parent.context == context => parent.depth + 1,
+            _ => 1,
+        };
+        self.expr_layers.push(ExprLayer {
+            id,
+            span,
+            context,
+            depth,
+            receiver,
+        });
+
+        if depth <= self.config.max_expr_depth {
+            return;
+        }
+
+        let outer_span = self
+            .expr_layers
+            .iter()
+            .rev()
+            .take_while(|layer| layer.context == context)
+            .last()
+            .map(|layer| layer.span);
+        let kind = self
+            .contexts
+            .last()
+            .map_or(ContextKind::Block, |ctx| ctx.kind);
+        let item = self.nested_item();
+        let arm = self.enclosing_arm();
+
+        let lint = NestingLint {
+            outer_span,
+            span,
+            kind,
+            reason: Reason::ExprDepth(depth),
+            item,
+            path: vec![],
+            arm,
+            suggestion: None,
+        };
+        let root = self.root_index();
+        Region::track(&mut self.expr_regions, root, id, lint);
+    }
+
+    /// Leaves the expression `id` if it is the innermost expression layer.
+    pub fn pop_expr_layer(&mut self, id: Id) {
+        if self.expr_layers.pop_if(|layer| layer.id == id).is_some() {
+            self.close_regions(id);
+        }
+    }
+
+    /// Closes the open regions rooted at the popped context `id`.
+    fn close_regions(&mut self, id: Id) {
+        let mut closed = Vec::new();
+        for regions in [
+            &mut self.depth_regions,
+            &mut self.closure_regions,
+            &mut self.item_regions,
+            &mut self.expr_regions,
+        ] {
+            closed.extend(regions.extract_if(.., |region| region.id == id));
+        }
+        for region in closed {
+            self.record_region(region.lint);
+        }
+    }
+
+    /// Keeps a closed region on the innermost item root until that item's body is done.
+    fn record_region(&mut self, lint: NestingLint) {
+        let Some(root) = self
+            .contexts
+            .iter_mut()
+            .rev()
+            .find(|ctx| ctx.kind.is_item_root())
+        else {
+            self.lints.push(lint);
+            return;
+        };
+        root.regions.push(lint);
+    }
+
+    /// Reports the regions of a finished item body according to the `report` setting,
+    /// selecting separately for each kind of budget.
+    fn report_regions(&mut self, regions: Vec<NestingLint>) {
+        if self.config.report == ReportMode::EveryRegion {
+            self.lints.extend(regions);
+            return;
+        }
+
+        let mut selected: Vec<NestingLint> = Vec::new();
+        for region in regions {
+            let same_reason = selected
+                .iter_mut()
+                .find(|lint| discriminant(&lint.reason) == discriminant(&region.reason));
+            match same_reason {
+                None => selected.push(region),
+                Some(lint)
+                    if self.config.report == ReportMode::Deepest
+                        && region.reason.count() > lint.reason.count() =>
+                {
+                    *lint = region;
+                }
+                Some(_) => {}
+            }
+        }
+        self.lints.extend(selected);
+    }
+
+    fn push_current_lints(&mut self, ctx: &mut Context<Id>) {
+        self.close_regions(ctx.id);
+
+        if ctx.kind.is_item_root() {
+            let regions = std::mem::take(&mut ctx.regions);
+            self.report_regions(regions);
+        }
+
+        if ctx.consec_if_branch_count > self.config.max_consec_if_else {
+            let item = self.nested_item();
+            // The chain's own span already starts at its first `if`; the outermost context of
+            // the item is unrelated when the chain is nested in another block.
+            self.lints.push(NestingLint {
+                outer_span: None,
+                span: ctx.span,
+                kind: ContextKind::If,
+                reason: Reason::ConsecIfElse(ctx.consec_if_branch_count),
+                item,
+                path: vec![],
+                arm: None,
+                suggestion: ctx.match_suggestion.take(),
+            });
+        }
+    }
+
+    /// Finds the `If` that starts the chain a branch of kind `popped` belongs to: the nearest
+    /// `If` that is not itself the `if` of an `else if`. A branch context always directly
+    /// follows its `If`, so that is the nearest `If` not followed by an `ElseIf`, or by the
+    /// popped branch if it was on top.
+    fn find_root_if_parent(&mut self, popped: ContextKind) -> Option<&mut Context<Id>> {
+        let root = self.root_index();
+        let mut end = self.contexts.len();
+        loop {
+            let index = root
+                + self.contexts[root..end]
+                    .iter()
+                    .rposition(|ctx| ctx.kind == ContextKind::If)?;
+            let branch = self.contexts.get(index + 1).map_or(popped, |ctx| ctx.kind);
+            if branch != ContextKind::ElseIf {
+                return Some(&mut self.contexts[index]);
+            }
+            end = index;
+        }
+    }
+
+    pub fn pop(&mut self) -> Context<Id> {
+        let mut ctx = self.contexts.pop().expect("pop context unchecked");
+
+        if ctx.kind.is_if_branch() {
+            if let Some(if_parent) = self.find_root_if_parent(ctx.kind) {
+                match ctx.kind {
+                    ContextKind::If => {
+                        // if_parent.consec_if_else_count += 1;
+                    }
+                    ContextKind::Then | ContextKind::ElseIf | ContextKind::Else => {
+                        // These can only exist within a ContextKind::If,
+                        // and are automatically "reset" when the current If is popped.
+                        if_parent.consec_if_branch_count += 1;
+                    }
+                    _ => {
+                        // if_parent.consec_if_else_count = 0;
+                    }
+                }
+            } else {
+                panic!("DID NOT FIND IF PARENT: {} {:?}", ctx.kind, ctx.id);
+            }
+        }
+
+        self.push_current_lints(&mut ctx);
+
+        ctx
+    }
+
+    /// Returns `true` if the expression is the body of the innermost context, which is treated
+    /// as that context rather than as a nested block.
+    pub fn is_item_body(&self, id: Id) -> bool {
+        self.contexts
+            .last()
+            .is_some_and(|ctx| ctx.body_id == Some(id))
+    }
+}
+
+impl<Id: Copy + PartialEq + Debug + Display> ContextStack<Id> {
+    /// Pushes a context and records it in the debug trace.
+    pub fn push_traced(&mut self, source_map: &SourceMap, ctx: Context<Id>) {
+        let (kind, id, span) = (ctx.kind, ctx.id, ctx.span);
+        self.push(ctx);
+
+        let depth = self.depth();
+        self.debug_trace_event(source_map, TraceEventKind::Push, id, kind, span, depth);
+    }
+
+    /// Pops the innermost context and records it in the debug trace.
+    pub fn pop_traced(&mut self, source_map: &SourceMap) -> Context<Id> {
+        let depth = self.depth();
+        let ctx = self.pop();
+        self.debug_trace_event(
+            source_map,
+            TraceEventKind::Pop,
+            ctx.id,
+            ctx.kind,
+            ctx.span,
+            depth,
+        );
+        ctx
+    }
+
+    /// Drops the ignored macro call sites recorded for a finished item and, once the stack is
+    /// empty again, renders and drops the debug trace of the top-level item, so neither grows
+    /// with the crate.
+    pub fn finish_item(&mut self) {
+        self.ignored_macro_call_sites.clear();
+        if !self.contexts.is_empty() {
+            return;
+        }
+        self.debug_render_trace();
+        self.trace_events.clear();
+    }
+}
@@ -0,0 +1,310 @@
+use rustc_ast::{BinOpKind, Expr, ExprKind, UnOp};
+use rustc_hir as hir;
+use rustc_lexer::{FrontmatterAllowed, TokenKind, tokenize};
+use rustc_lint::LintContext;
+use rustc_span::{ExpnKind, Ident, MacroKind, Span};
+use std::collections::HashSet;
+
+/// Extra indentation for each arm of the generated `match`.
+const ARM_INDENT: &str = "    ";
+
+/// Name of the `matches!` macro, whose expansion is recognized as a pattern test.
+const MATCHES_MACRO: &str = "matches";
+
+/// One condition of an if/else-if chain, split into the value it tests and the pattern that
+/// value is tested against, with the block the condition guards.
+struct ChainArm {
+    scrutinee: Span,
+    pattern: Span,
+    body: Span,
+}
+
+/// Shape of an `if` condition that tests a value against a pattern.
+pub enum Condition<'a, E> {
+    /// `lhs == rhs`, where either side may be the pattern
+    Eq(&'a E, &'a E),
+    /// `let pattern = scrutinee`, or the single arm of a `matches!`
+    Pattern(&'a E, Span),
+}
+
+/// Expression of the AST (early pass) or of the HIR (late pass), as far as the `match`
+/// suggestion looks into it.
+pub trait ChainExpr: Sized {
+    fn span(&self) -> Span;
+
+    /// Condition, then block and `else` expression of an `if`.
+    fn as_if(&self) -> Option<(&Self, Span, Option<&Self>)>;
+
+    fn is_block(&self) -> bool;
+
+    /// The condition, if it is a comparison or pattern test.
+    fn condition(&self) -> Option<Condition<'_, Self>>;
+
+    /// Returns `true` if the expression can be written as a `match` pattern as-is: a
+    /// literal, a negated literal, or a path that looks like a constant or unit variant.
+    fn is_pattern_expr(&self) -> bool;
+
+    /// Returns `true` for a path or a field access on one. Only such a scrutinee reads the
+    /// same value in every condition of the chain; a call like `next()` may return a
+    /// different value each time it is evaluated, so the chain cannot be merged into one
+    /// `match`.
+    fn is_path_or_field(&self) -> bool;
+}
+
+/// Builds a `match` that replaces an if/else-if chain whose conditions all test the same
+/// scrutinee against a literal or pattern (`x == 1`, `let Some(..) = x`, `matches!(x, ..)`).
+///
+/// Returns `None` if any condition in the chain does not fit that shape, if the scrutinee is
+/// not a path or field access, if the scrutinees differ, or if any part of the chain has no
+/// source snippet.
+pub fn if_chain_match_suggestion(cx: &impl LintContext, expr: &impl ChainExpr) -> Option<String> {
+    let mut arms = Vec::new();
+    let mut current = expr;
+    loop {
+        let (cond, body, else_expr) = current.as_if()?;
+        let (scrutinee, pattern) = scrutinee_and_pattern(cond)?;
+        arms.push(ChainArm {
+            scrutinee,
+            pattern,
+            body,
+        });
+
+        let Some(else_expr) = else_expr else {
+            return render_match(cx, expr.span(), &arms, None);
+        };
+        if else_expr.is_block() {
+            return render_match(cx, expr.span(), &arms, Some(else_expr.span()));
+        }
+        current = else_expr;
+    }
+}
+
+/// Renders the arms of a chain starting at `span` as a `match`, with the final `else` block
+/// (or `{}`) as the wildcard arm.
+fn render_match(
+    cx: &impl LintContext,
+    span: Span,
+    arms: &[ChainArm],
+    else_body: Option<Span>,
+) -> Option<String> {
+    if arms.len() < 2 {
+        return None;
+    }
+
+    let source_map = cx.sess().source_map();
+    let snippet = |span: Span| source_map.span_to_snippet(span).ok();
+    let indent = source_map.indentation_before(span).unwrap_or_default();
+
+    let scrutinee = snippet(arms.first()?.scrutinee)?;
+    let mut patterns = Vec::new();
+    for arm in arms {
+        if snippet(arm.scrutinee)? != scrutinee {
+            return None;
+        }
+        patterns.push((snippet(arm.pattern)?, snippet(arm.body)?));
+    }
+    let else_body = match else_body {
+        Some(else_body) => snippet(else_body)?,
+        None => "{}".to_string(),
+    };
+
+    let mut suggestion = format!("match {scrutinee} {{\n");
+    for (pattern, body) in patterns
+        .iter()
+        .map(|(pattern, body)| (pattern.as_str(), body))
+        .chain(std::iter::once(("_", &else_body)))
+    {
+        let body = indent_arm_body(body);
+        suggestion.push_str(&format!("{indent}{ARM_INDENT}{pattern} => {body}\n"));
+    }
+    suggestion.push_str(&format!("{indent}}}"));
+    Some(suggestion)
+}
+
+/// Indents every line of `body` after the first by [`ARM_INDENT`], except lines inside
+/// multi-line string literals, whose indentation is part of the string.
+fn indent_arm_body(body: &str) -> String {
+    source_lines(body)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (line, in_literal))| {
+            if i == 0 || in_literal {
+                line.to_string()
+            } else {
+                format!("{ARM_INDENT}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Lines of `text`, each paired with whether it starts inside a multi-line string literal.
+fn source_lines(text: &str) -> Vec<(&str, bool)> {
+    let mut literal_line_starts = HashSet::new();
+    let mut offset = 0;
+    for token in tokenize(text, FrontmatterAllowed::No) {
+        let end = offset + token.len as usize;
+        if let TokenKind::Literal { .. } = token.kind {
+            let newlines = text[offset..end].match_indices('\n');
+            literal_line_starts.extend(newlines.map(|(index, _)| offset + index + 1));
+        }
+        offset = end;
+    }
+    let line_starts =
+        std::iter::once(0).chain(text.match_indices('\n').map(|(index, _)| index + 1));
+    text.split('\n')
+        .zip(line_starts)
+        .map(|(line, start)| (line, literal_line_starts.contains(&start)))
+        .collect()
+}
+
+/// Splits a single if condition into the spans of its scrutinee and the pattern it is tested
+/// against.
+fn scrutinee_and_pattern(cond: &impl ChainExpr) -> Option<(Span, Span)> {
+    let (scrutinee, pattern) = match cond.condition()? {
+        Condition::Eq(lhs, rhs) => match (lhs.is_pattern_expr(), rhs.is_pattern_expr()) {
+            (false, true) => (lhs, rhs.span()),
+            (true, false) => (rhs, lhs.span()),
+            _ => return None,
+        },
+        Condition::Pattern(scrutinee, pattern) => (scrutinee, pattern),
+    };
+    scrutinee
+        .is_path_or_field()
+        .then_some((scrutinee.span(), pattern))
+}
+
+impl ChainExpr for Expr {
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn as_if(&self) -> Option<(&Self, Span, Option<&Self>)> {
+        let ExprKind::If(cond, block, else_expr) = &self.kind else {
+            return None;
+        };
+        Some((cond, block.span, else_expr.as_deref()))
+    }
+
+    fn is_block(&self) -> bool {
+        matches!(self.kind, ExprKind::Block(..))
+    }
+
+    fn condition(&self) -> Option<Condition<'_, Self>> {
+        match &self.kind {
+            ExprKind::Paren(inner) => inner.condition(),
+            ExprKind::Binary(op, lhs, rhs) if op.node == BinOpKind::Eq => {
+                Some(Condition::Eq(lhs, rhs))
+            }
+            ExprKind::Let(pat, scrutinee, ..) => Some(Condition::Pattern(scrutinee, pat.span)),
+            ExprKind::Match(scrutinee, arms, _) if is_matches_macro(self.span) => {
+                let [arm, _] = arms.as_slice() else {
+                    return None;
+                };
+                arm.guard
+                    .is_none()
+                    .then_some(Condition::Pattern(scrutinee, arm.pat.span))
+            }
+            _ => None,
+        }
+    }
+
+    fn is_pattern_expr(&self) -> bool {
+        match &self.kind {
+            ExprKind::Lit(_) => true,
+            ExprKind::Unary(UnOp::Neg, inner) => matches!(inner.kind, ExprKind::Lit(_)),
+            ExprKind::Path(None, path) => path
+                .segments
+                .last()
+                .is_some_and(|segment| is_constant_name(segment.ident)),
+            _ => false,
+        }
+    }
+
+    fn is_path_or_field(&self) -> bool {
+        match &self.kind {
+            ExprKind::Path(..) => true,
+            ExprKind::Field(base, _) | ExprKind::Paren(base) => base.is_path_or_field(),
+            _ => false,
+        }
+    }
+}
+
+/// Looks through the `DropTemps` that HIR wraps plain `if` conditions in.
+impl<'hir> ChainExpr for hir::Expr<'hir> {
+    fn span(&self) -> Span {
+        self.span
+    }
+
+    fn as_if(&self) -> Option<(&Self, Span, Option<&Self>)> {
+        let hir::ExprKind::If(cond, then, else_expr) = self.kind else {
+            return None;
+        };
+        Some((cond, then.span, else_expr))
+    }
+
+    fn is_block(&self) -> bool {
+        matches!(self.kind, hir::ExprKind::Block(..))
+    }
+
+    fn condition(&self) -> Option<Condition<'_, Self>> {
+        match self.kind {
+            hir::ExprKind::DropTemps(inner) => inner.condition(),
+            hir::ExprKind::Binary(op, lhs, rhs) if op.node == BinOpKind::Eq => {
+                Some(Condition::Eq(lhs, rhs))
+            }
+            hir::ExprKind::Let(let_expr) => {
+                Some(Condition::Pattern(let_expr.init, let_expr.pat.span))
+            }
+            hir::ExprKind::Match(scrutinee, arms, hir::MatchSource::Normal)
+                if is_matches_macro(self.span) =>
+            {
+                let [arm, _] = arms else {
+                    return None;
+                };
+                arm.guard
+                    .is_none()
+                    .then_some(Condition::Pattern(scrutinee, arm.pat.span))
+            }
+            _ => None,
+        }
+    }
+
+    fn is_pattern_expr(&self) -> bool {
+        match self.kind {
+            hir::ExprKind::Lit(_) => true,
+            hir::ExprKind::Unary(hir::UnOp::Neg, inner) => {
+                matches!(inner.kind, hir::ExprKind::Lit(_))
+            }
+            hir::ExprKind::Path(hir::QPath::Resolved(None, path)) => path
+                .segments
+                .last()
+                .is_some_and(|segment| is_constant_name(segment.ident)),
+            _ => false,
+        }
+    }
+
+    fn is_path_or_field(&self) -> bool {
+        match self.kind {
+            hir::ExprKind::Path(_) => true,
+            hir::ExprKind::Field(base, _) => base.is_path_or_field(),
+            _ => false,
+        }
+    }
+}
+
+/// Returns `true` for a name that looks like a constant or unit variant.
+fn is_constant_name(ident: Ident) -> bool {
+    ident.name.as_str().starts_with(char::is_uppercase)
+}
+
+/// Returns `true` if the span comes directly from a `matches!` invocation.
+fn is_matches_macro(span: Span) -> bool {
+    if !span.from_expansion() {
+        return false;
+    }
+    matches!(
+        span.ctxt().outer_expn_data().kind,
+        ExpnKind::Macro(MacroKind::Bang, name) if name.as_str() == MATCHES_MACRO
+    )
+}
@@ -1,4 +1,4 @@
-#![allow(unused, clippy::collapsible_if, clippy::single_match)]
+#![allow(unused, clippy::collapsible_if, clippy::collapsible_else_if, clippy::single_match)]
 use std::sync::LazyLock;
 
 static LAZY_VALUE: LazyLock<i32> = LazyLock::new(|| {
@@ -154,9 +154,9 @@ fn seven() {
             println!("y < 1");
             if y < 2 {
                 println!("y < 2");
-                //~v ERROR: 6 levels
                 if y < 5 {
                     println!("y < 5");
+                    //~v ERROR: 5 levels
                     if y < 10 {
                         println!("y < 10");
                         if y < 20 {
@@ -178,7 +178,7 @@ fn seven() {
         }
     }
     let x = 1;
-    //~v ERROR: 22 found
+    //~v ERROR: 11 found
     if x < 1 {
         println!("x < 1");
         if x < 2 {
@@ -231,6 +231,38 @@ fn seven() {
     }
 }
 
+// The chain is counted on its own `if`, not on the `if` whose block it is in
+fn if_chain_in_then(x: i32, enabled: bool) {
+    if enabled {
+        //~v ERROR: 12 found
+        if x == 1 {
+            println!("one");
+        } else if x == 2 {
+            println!("two");
+        } else if x == 3 {
+            println!("three");
+        } else if x == 4 {
+            println!("four");
+        } else if x == 5 {
+            println!("five");
+        } else if x == 6 {
+            println!("six");
+        } else if x == 7 {
+            println!("seven");
+        } else if x == 8 {
+            println!("eight");
+        } else if x == 9 {
+            println!("nine");
+        } else if x == 10 {
+            println!("ten");
+        } else if x == 11 {
+            println!("eleven");
+        } else {
+            println!("many");
+        }
+    }
+}
+
 fn main() {
     // Force the LazyLock to initialize to test the nesting
     let _value = *LAZY_VALUE;
@@ -240,6 +272,7 @@ fn main() {
     five();
     six();
     seven();
+    if_chain_in_then(3, true);
     // Additional edge cases
     edge_let_else_reduction();
     edge_deep_only_in_final_else(5);
@@ -392,6 +425,7 @@ fn edge_macro_local(v: i32) {
 fn edge_multiple_closures_layers(x: i32) {
     let outer = || {
         let mid = || {
+            //~v ERROR: closure nesting depth: 2 max allowed, 3 levels found
             let inner = || {
                 // if ignore_closures == false:
                 // (~v ERROR: 7 levels)
@@ -428,3 +462,489 @@ fn edge_partial_deep_path(cond: bool) {
         let _ = cond;
     }
 }
+
+fn edge_consec_if_else_same_scrutinee(x: i32) {
+    //~v ERROR: 12 found
+    if x == 1 {
+        println!("one");
+    } else if x == 2 {
+        println!("two");
+    } else if 3 == x {
+        println!("three");
+    } else if x == 4 {
+        println!("four");
+    } else if x == 5 {
+        println!("five");
+    } else if x == 6 {
+        println!("six");
+    } else if x == 7 {
+        println!("seven");
+    } else if x == 8 {
+        println!("eight");
+    } else if x == -9 {
+        println!("minus nine");
+    } else if matches!(x, 10 | 11) {
+        println!("ten or eleven");
+    } else if let 12..=20 = x {
+        println!("twelve to twenty");
+    } else {
+        println!("other");
+    }
+}
+
+fn edge_consec_if_else_mixed_conditions(x: i32, y: i32) {
+    //~v ERROR: 12 found
+    if x == 1 {
+        println!("one");
+    } else if y == 2 {
+        println!("two");
+    } else if x == 3 {
+        println!("three");
+    } else if x == 4 {
+        println!("four");
+    } else if x == 5 {
+        println!("five");
+    } else if x == 6 {
+        println!("six");
+    } else if x == 7 {
+        println!("seven");
+    } else if x == 8 {
+        println!("eight");
+    } else if x == 9 {
+        println!("nine");
+    } else if x == 10 {
+        println!("ten");
+    } else if x > 10 {
+        println!("big");
+    } else {
+        println!("other");
+    }
+}
+
+fn edge_async_closure_layers(values: Vec<i32>) {
+    let process = async move || {
+        let task = async move {
+            //~v ERROR: closure nesting depth: 2 max allowed, 3 levels found
+            let add_one = |v: i32| {
+                let _ = v;
+                v + 1
+            };
+            //~v ERROR: closure nesting depth: 2 max allowed, 3 levels found
+            values.iter().map(|v| add_one(*v)).sum::<i32>()
+        };
+        task.await
+    };
+    let _ = process;
+}
+
+fn edge_nested_fn_fresh_depth(v: i32) {
+    if v > 0 {
+        if v > 1 {
+            fn helper(w: i32) {
+                if w > 0 {
+                    if w > 1 {
+                        if w > 2 {
+                            let _ = w;
+                        }
+                    }
+                }
+            }
+            helper(v);
+        }
+    }
+}
+
+fn edge_nested_fn_too_deep(v: i32) {
+    if v > 0 {
+        fn helper(w: i32) {
+            if w > 0 {
+                if w > 1 {
+                    if w > 2 {
+                        //~v ERROR: 4 levels
+                        if w > 3 {
+                            let _ = w;
+                        }
+                    }
+                }
+            }
+        }
+        helper(v);
+    }
+}
+
+fn edge_impl_in_fn_body(v: i32) {
+    struct Local;
+    impl Local {
+        fn check(w: i32) {
+            if w > 0 {
+                if w > 1 {
+                    if w > 2 {
+                        //~v ERROR: 4 levels
+                        if w > 3 {
+                            let _ = w;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if v > 0 {
+        if v > 1 {
+            Local::check(v);
+        }
+    }
+}
+
+fn edge_closure_returning_items(v: i32) {
+    let make = || {
+        if v > 0 {
+            if v > 1 {
+                fn inner(w: i32) -> i32 {
+                    if w > 0 {
+                        if w > 1 { w } else { 1 }
+                    } else {
+                        0
+                    }
+                }
+                return inner(v);
+            }
+        }
+        0
+    };
+    let _ = make();
+}
+
+const EDGE_CONST: i32 = {
+    let v = 3;
+    if v > 0 {
+        if v > 1 {
+            if v > 2 {
+                //~v ERROR: 4 levels
+                if v > 3 { 4 } else { 3 }
+            } else {
+                2
+            }
+        } else {
+            1
+        }
+    } else {
+        0
+    }
+};
+
+fn edge_const_block_fresh_depth(v: i32) {
+    if v > 0 {
+        if v > 1 {
+            let _ = const {
+                let w = 2;
+                if w > 0 {
+                    if w > 1 { w } else { 1 }
+                } else {
+                    0
+                }
+            };
+        }
+    }
+}
+
+fn edge_two_deep_regions(v: i32) {
+    if v > 0 {
+        if v > 1 {
+            if v > 2 {
+                //~v ERROR: 4 levels
+                if v > 3 {}
+            }
+        }
+    }
+    if v < 0 {
+        if v < -1 {
+            if v < -2 {
+                //~v ERROR: 5 levels
+                if v < -3 {
+                    if v < -4 {}
+                }
+            }
+        }
+    }
+}
+
+fn edge_deeper_sibling_in_region(v: i32) {
+    if v > 0 {
+        if v > 1 {
+            if v > 2 {
+                //~v ERROR: 4 to 6 levels
+                if v > 3 {
+                    if v > 4 {
+                        let _ = v;
+                    }
+                    if v > 5 {
+                        if v > 6 {}
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn edge_too_many_match_arms(v: i32) -> i32 {
+    //~v ERROR: match arms: 25 max allowed, 27 found
+    match v {
+        0 => 0,
+        1 => 1,
+        2 => 2,
+        3 => 3,
+        4 => 4,
+        5 => 5,
+        6 => 6,
+        7 => 7,
+        8 => 8,
+        9 => 9,
+        10 => 10,
+        11 => 11,
+        12 => 12,
+        13 => 13,
+        14 => 14,
+        15 => 15,
+        16 => 16,
+        17 => 17,
+        18 => 18,
+        19 => 19,
+        20 => 20,
+        21 => 21,
+        22 => 22,
+        23 => 23,
+        24 => 24,
+        25 => 25,
+        _ => -1,
+    }
+}
+
+fn edge_long_match_arm(v: i32) {
+    match v {
+        //~v ERROR: match arm items: 20 max allowed, 21 found
+        0 => {
+            let _ = v;
+            let _ = v + 1;
+            let _ = v + 2;
+            let _ = v + 3;
+            let _ = v + 4;
+            let _ = v + 5;
+            let _ = v + 6;
+            let _ = v + 7;
+            let _ = v + 8;
+            let _ = v + 9;
+            let _ = v + 10;
+            let _ = v + 11;
+            let _ = v + 12;
+            let _ = v + 13;
+            let _ = v + 14;
+            let _ = v + 15;
+            let _ = v + 16;
+            let _ = v + 17;
+            let _ = v + 18;
+            let _ = v + 19;
+            let _ = v + 20;
+        }
+        _ => {}
+    }
+}
+
+fn edge_match_in_arm_too_deep(v: Option<i32>) {
+    if let Some(v) = v {
+        match v {
+            0 => {}
+            n => match n {
+                //~v ERROR: 4 levels
+                1 => {
+                    let _ = n;
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+mod edge_outer_mod {
+    mod inner {
+        mod innermost {
+            fn too_deep() {
+                //~v ERROR: item nesting depth: 4 max allowed, 5 levels found
+                fn nested() {}
+                nested();
+            }
+        }
+    }
+}
+
+fn edge_expr_nesting_too_deep(map: &std::collections::HashMap<i32, i32>, k: i32) {
+    //~v ERROR: expression nesting depth: 4 max allowed, 5 levels found
+    let _ = Some(Some(Some(Some(vec![map.get(&k).unwrap()]))));
+}
+
+fn edge_expr_method_chain_is_one_level(v: &[i32]) -> Vec<String> {
+    v.iter().map(|x| x + 1).filter(|x| *x > 0).map(|x| x.to_string()).collect()
+}
+
+struct ExprNode {
+    next: Option<Box<ExprNode>>,
+}
+
+fn edge_expr_nested_struct_literals() -> ExprNode {
+    ExprNode {
+        next: Some(Box::new(ExprNode {
+            //~v ERROR: 5 to 7 levels
+            next: Some(Box::new(ExprNode { next: None })),
+        })),
+    }
+}
+
+fn edge_expr_block_restarts_count(x: i32) {
+    let _ = Some(Some(Some({
+        let y = x;
+        Some(Some(Some(y)))
+    })));
+}
+
+fn edge_nested_closures_iterator_adaptors(groups: &[Vec<i32>]) -> Vec<i32> {
+    groups
+        .iter()
+        .map(|group| {
+            let doubled = group.iter().filter(|v| {
+                let v = **v;
+                v > 0
+            });
+            doubled.sum()
+        })
+        .collect()
+}
+
+fn edge_nested_closures_third_level_in_adaptors(groups: &[Vec<i32>]) -> Vec<i32> {
+    groups
+        .iter()
+        .map(|group| {
+            let positive = group.iter().filter(|v| {
+                //~v ERROR: closure nesting depth: 2 max allowed, 3 levels found
+                let check = |v: i32| {
+                    let zero = 0;
+                    v > zero
+                };
+                check(**v)
+            });
+            positive.sum()
+        })
+        .collect()
+}
+
+fn edge_nested_closures_not_adaptor(values: &[i32]) -> i32 {
+    let total = || {
+        values.iter().fold(0, |sum, v| {
+            let v = *v;
+            sum + v
+        })
+    };
+    total()
+}
+
+fn edge_consec_if_else_call_scrutinee(mut next: impl FnMut() -> i32) {
+    //~v ERROR: 12 found
+    if next() == 1 {
+        println!("one");
+    } else if next() == 2 {
+        println!("two");
+    } else if next() == 3 {
+        println!("three");
+    } else if next() == 4 {
+        println!("four");
+    } else if next() == 5 {
+        println!("five");
+    } else if next() == 6 {
+        println!("six");
+    } else if next() == 7 {
+        println!("seven");
+    } else if next() == 8 {
+        println!("eight");
+    } else if next() == 9 {
+        println!("nine");
+    } else if next() == 10 {
+        println!("ten");
+    } else if next() == 11 {
+        println!("eleven");
+    } else {
+        println!("other");
+    }
+}
+
+fn edge_nested_fn_in_deep_region(v: i32) {
+    if v > 0 {
+        if v > 1 {
+            if v > 2 {
+                //~v ERROR: 4 levels
+                if v > 3 {
+                    fn helper(w: i32) {
+                        if w > 0 {
+                            if w > 1 {
+                                if w > 2 {
+                                    //~v ERROR: 4 levels
+                                    if w > 3 {
+                                        let _ = w;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    helper(v);
+                }
+            }
+        }
+    }
+}
+
+fn edge_nested_closures_with_expression_bodies(groups: &[Vec<i32>], x: i32) -> i32 {
+    let run = || {
+        let apply = |x: i32| {
+            //~v ERROR: closure nesting depth: 2 max allowed, 3 levels found
+            let f = |y| y + 1;
+            f(x)
+        };
+        apply(x)
+    };
+    let sums = || {
+        groups
+            .iter()
+            //~v ERROR: closure nesting depth: 2 max allowed, 3 levels found
+            .map(|g| g.iter().map(|v| v * 2).sum::<i32>())
+            .sum::<i32>()
+    };
+    run() + sums()
+}
+
+fn edge_consec_if_else_multiline_string(x: i32) {
+    //~v ERROR: 12 found
+    if x == 1 {
+        println!("one");
+    } else if x == 2 {
+        let text = "two
+    lines";
+        println!("{text}");
+    } else if x == 3 {
+        println!("three");
+    } else if x == 4 {
+        println!("four");
+    } else if x == 5 {
+        println!("five");
+    } else if x == 6 {
+        println!("six");
+    } else if x == 7 {
+        println!("seven");
+    } else if x == 8 {
+        println!("eight");
+    } else if x == 9 {
+        println!("nine");
+    } else if x == 10 {
+        println!("ten");
+    } else if x == 11 {
+        println!("eleven");
+    } else {
+        println!("other");
+    }
+}
@@ -0,0 +1,178 @@
+#![allow(
+    unused,
+    clippy::collapsible_if,
+    clippy::collapsible_else_if,
+    clippy::never_loop,
+    clippy::single_match
+)]
+
+fn main() {}
+
+fn loops_count_as_levels(items: &[i32]) {
+    for item in items {
+        let mut n = *item;
+        while n > 0 {
+            loop {
+                //~v ERROR: 4 levels
+                if n % 2 == 0 {
+                    n -= 1;
+                }
+                break;
+            }
+            n -= 1;
+        }
+    }
+}
+
+fn question_mark_is_not_a_match(v: Option<i32>) -> Option<i32> {
+    if v.is_some() {
+        if v? > 1 {
+            if v? > 2 {
+                return Some(v? + 1);
+            }
+        }
+    }
+    None
+}
+
+async fn async_fn_body_is_not_an_async_block(v: i32) -> i32 {
+    if v > 0 {
+        if v > 1 {
+            if v > 2 {
+                return ready(v).await;
+            }
+        }
+    }
+    0
+}
+
+async fn ready(v: i32) -> i32 {
+    v
+}
+
+fn async_block_in_closures(v: i32) {
+    let outer = || {
+        let fut = async move {
+            //~v ERROR: closure nesting depth: 2 max allowed, 3 levels found
+            let inner = || {
+                let _ = v;
+            };
+            inner();
+        };
+        drop(fut);
+    };
+    outer();
+}
+
+fn else_if_chain(v: i32) {
+    if v == 0 {
+        let _ = v;
+    } else if v == 1 {
+        if v > 2 {
+            if v > 3 {
+                //~v ERROR: 4 levels
+                if v > 4 {
+                    let _ = v;
+                }
+            }
+        }
+    }
+}
+
+fn match_in_for_arm(items: &[Option<i32>]) {
+    for item in items {
+        match item {
+            Some(v) => {
+                //~v ERROR: 4 levels
+                if *v > 0 {
+                    let _ = v;
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+fn expr_nesting_too_deep(map: &std::collections::HashMap<i32, i32>, k: i32) {
+    //~v ERROR: expression nesting depth: 4 max allowed, 5 levels found
+    let _ = Some(Some(Some(Some(vec![map.get(&k).unwrap()]))));
+}
+
+fn question_mark_and_for_are_not_expr_layers(v: &[i32]) -> Option<i32> {
+    let mut total = 0;
+    for x in v.iter().map(|x| Some(*x)) {
+        total += Some(Some(Some(Some(x?))))????;
+    }
+    Some(total)
+}
+
+fn nested_closures_iterator_adaptors(groups: &[Vec<i32>]) -> Vec<i32> {
+    groups
+        .iter()
+        .map(|group| {
+            let doubled = group.iter().filter(|v| {
+                let v = **v;
+                v > 0
+            });
+            doubled.sum()
+        })
+        .collect()
+}
+
+fn nested_closures_not_adaptor(values: &[i32]) -> i32 {
+    let total = || {
+        values.iter().fold(0, |sum, v| {
+            let v = *v;
+            sum + v
+        })
+    };
+    total()
+}
+
+fn consec_if_else_field_scrutinee(pair: (i32, i32)) {
+    //~v ERROR: 12 found
+    if pair.0 == 1 {
+        println!("one");
+    } else if pair.0 == 2 {
+        println!("two");
+    } else if pair.0 == 3 {
+        println!("three");
+    } else if pair.0 == 4 {
+        println!("four");
+    } else if pair.0 == 5 {
+        println!("five");
+    } else if pair.0 == 6 {
+        println!("six");
+    } else if pair.0 == 7 {
+        println!("seven");
+    } else if pair.0 == 8 {
+        println!("eight");
+    } else if matches!(pair.0, 9 | 10) {
+        println!("nine or ten");
+    } else if let 11..=20 = pair.0 {
+        println!("eleven to twenty");
+    } else if pair.0 == -1 {
+        println!("minus one");
+    } else {
+        println!("other");
+    }
+}
+
+fn nested_closures_with_expression_bodies(groups: &[Vec<i32>], x: i32) -> i32 {
+    let run = || {
+        let apply = |x: i32| {
+            //~v ERROR: closure nesting depth: 2 max allowed, 3 levels found
+            let f = |y| y + 1;
+            f(x)
+        };
+        apply(x)
+    };
+    let sums = || {
+        groups
+            .iter()
+            //~v ERROR: closure nesting depth: 2 max allowed, 3 levels found
+            .map(|g| g.iter().map(|v| v * 2).sum::<i32>())
+            .sum::<i32>()
+    };
+    run() + sums()
+}
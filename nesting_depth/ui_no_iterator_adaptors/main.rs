@@ -0,0 +1,25 @@
+#![allow(unused)]
+
+fn main() {}
+
+fn iterator_adaptors(groups: &[Vec<i32>]) -> Vec<i32> {
+    groups
+        .iter()
+        .map(|group| {
+            //~v ERROR: closure nesting depth: 1 max allowed, 2 levels found
+            let doubled = group.iter().filter(|v| {
+                let v = **v;
+                v > 0
+            });
+            doubled.sum()
+        })
+        .collect()
+}
+
+fn iterator_consumers(groups: &[Vec<i32>]) -> i32 {
+    groups
+        .iter()
+        //~v ERROR: closure nesting depth: 1 max allowed, 2 levels found
+        .map(|group| group.iter().fold(0, |total, v| total + v))
+        .sum()
+}
@@ -0,0 +1,28 @@
+#![allow(unused, clippy::collapsible_if)]
+
+fn two_regions(v: i32) {
+    if v > 0 {
+        if v > 1 {
+            if v > 2 {
+                // `report = "deepest"` leaves out the shallower region before the deepest.
+                if v > 3 {
+                    println!("four levels");
+                }
+            }
+        }
+    }
+    if v < 0 {
+        if v < -1 {
+            if v < -2 {
+                //~v ERROR: 4 to 5 levels
+                if v < -3 {
+                    if v < -4 {
+                        println!("five levels");
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn main() {}
@@ -0,0 +1,28 @@
+#![allow(unused, clippy::collapsible_if)]
+
+fn two_regions(v: i32) {
+    if v > 0 {
+        if v > 1 {
+            if v > 2 {
+                //~v ERROR: 4 levels
+                if v > 3 {
+                    println!("four levels");
+                }
+            }
+        }
+    }
+    if v < 0 {
+        if v < -1 {
+            if v < -2 {
+                // `report = "first"` leaves out the deeper region after the first.
+                if v < -3 {
+                    if v < -4 {
+                        println!("five levels");
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn main() {}
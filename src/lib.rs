@@ -10,6 +10,9 @@ extern crate rustc_session;
 #[unsafe(no_mangle)]
 pub fn register_lints(sess: &rustc_session::Session, lint_store: &mut rustc_lint::LintStore) {
     eol_comments::register_lints(sess, lint_store);
+    function_length::register_lints(sess, lint_store);
+    needless_else::register_lints(sess, lint_store);
     nesting_depth::register_lints(sess, lint_store);
+    type_nesting_depth::register_lints(sess, lint_store);
     uninlined_format_args::register_lints(sess, lint_store);
 }
@@ -0,0 +1,38 @@
+use serde::Deserialize;
+use serde_inline_default::serde_inline_default;
+
+pub const HELP_MESSAGE: &str = "introduce a type alias or a newtype for an inner part of the type";
+
+/// Label for the largest inner type that fits within `max_type_depth`.
+pub const NAME_LABEL: &str = "this part could be named";
+
+/// Default maximum generic nesting levels
+const DEFAULT_MAX_TYPE_DEPTH: usize = 3;
+
+/// Default wrappers that do not count as a nesting level
+fn default_transparent_wrappers() -> Vec<String> {
+    ["Box", "Rc", "Arc"].map(String::from).to_vec()
+}
+
+/// Lint configuration
+#[serde_inline_default]
+#[derive(Deserialize)]
+pub struct Config {
+    /// Maximum allowed generic types nested within each other
+    #[serde_inline_default(DEFAULT_MAX_TYPE_DEPTH)]
+    pub max_type_depth: usize,
+
+    /// Names of generic types that do not count as a nesting level, matched against the last
+    /// path segment (`Box` matches both `Box<T>` and `std::boxed::Box<T>`)
+    #[serde(default = "default_transparent_wrappers")]
+    pub transparent_wrappers: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_type_depth: DEFAULT_MAX_TYPE_DEPTH,
+            transparent_wrappers: default_transparent_wrappers(),
+        }
+    }
+}
@@ -0,0 +1,315 @@
+#![feature(rustc_private)]
+#![warn(unused_extern_crates)]
+
+mod config;
+
+extern crate rustc_ast;
+extern crate rustc_lint;
+extern crate rustc_session;
+extern crate rustc_span;
+
+#[cfg(not(feature = "rlib"))]
+dylint_linting::dylint_library!();
+
+const DESCRIPTION: &str = "excessive generic type nesting";
+
+use config::{Config, HELP_MESSAGE, NAME_LABEL};
+use dylint_linting::config_or_default;
+use rustc_ast::{
+    AngleBracketedArg, AssocItem, AssocItemConstraintKind, AssocItemKind, FieldDef, FnDecl,
+    FnRetTy, GenericArg, GenericArgs, GenericBound, Item, ItemKind, Local, NodeId, Path, Term, Ty,
+    TyKind, Variant, VariantData,
+    visit::{AssocCtxt, FnCtxt, FnKind},
+};
+use rustc_lint::{EarlyContext, EarlyLintPass, LintContext, LintStore};
+use rustc_session::{Session, declare_lint, impl_lint_pass};
+use rustc_span::Span;
+
+/// Lint for generic types nested too many levels deep
+pub struct TypeNestingDepth {
+    config: Config,
+}
+
+impl Default for TypeNestingDepth {
+    fn default() -> Self {
+        Self {
+            config: config_or_default(env!("CARGO_PKG_NAME")),
+        }
+    }
+}
+
+#[allow(clippy::no_mangle_with_rust_abi)]
+#[cfg_attr(not(feature = "rlib"), unsafe(no_mangle))]
+pub fn register_lints(sess: &Session, lint_store: &mut LintStore) {
+    dylint_linting::init_config(sess);
+    lint_store.register_lints(&[TYPE_NESTING_DEPTH]);
+    lint_store.register_early_pass(|| Box::new(TypeNestingDepth::default()));
+}
+
+declare_lint! {
+    /// ### What it does
+    /// Checks for generic types nested too many levels deep in fn signatures, struct and enum
+    /// fields, type aliases and `let` annotations. References, slices, arrays and tuples do not
+    /// count as a level, nor do the configured transparent wrappers (`Box`, `Rc` and `Arc` by
+    /// default).
+    ///
+    /// ### Why is this bad?
+    /// Deeply nested types are hard to read and are usually repeated wherever the value is
+    /// passed around. A type alias or newtype gives the inner part a name.
+    ///
+    /// ### Example
+    /// ```rust,no_run
+    /// # use std::collections::HashMap;
+    /// # use std::sync::{Arc, Mutex};
+    /// struct Registry {
+    ///     handlers: Arc<Mutex<HashMap<String, Vec<Option<Result<Box<dyn Fn()>, String>>>>>>,
+    /// }
+    /// ```
+    ///
+    /// Use instead:
+    /// ```rust,no_run
+    /// # use std::collections::HashMap;
+    /// # use std::sync::{Arc, Mutex};
+    /// type Handler = Option<Result<Box<dyn Fn()>, String>>;
+    ///
+    /// struct Registry {
+    ///     handlers: Arc<Mutex<HashMap<String, Vec<Handler>>>>,
+    /// }
+    /// ```
+    pub TYPE_NESTING_DEPTH,
+    Warn,
+    DESCRIPTION
+}
+
+impl_lint_pass!(TypeNestingDepth => [TYPE_NESTING_DEPTH]);
+
+/// Generic nesting of a type.
+#[derive(Default)]
+struct Nesting {
+    /// Spans of the counted types on the deepest path, from the outermost to the innermost.
+    path: Vec<Span>,
+}
+
+impl Nesting {
+    fn depth(&self) -> usize {
+        self.path.len()
+    }
+}
+
+/// The deepest of the given nestings, the first one on a tie.
+fn deepest(nestings: impl IntoIterator<Item = Nesting>) -> Nesting {
+    nestings
+        .into_iter()
+        .reduce(|deepest, nesting| {
+            if nesting.depth() > deepest.depth() {
+                nesting
+            } else {
+                deepest
+            }
+        })
+        .unwrap_or_default()
+}
+
+impl TypeNestingDepth {
+    fn ty_nesting(&self, ty: &Ty) -> Nesting {
+        match &ty.kind {
+            TyKind::Slice(inner)
+            | TyKind::Array(inner, _)
+            | TyKind::Paren(inner)
+            | TyKind::Pat(inner, _) => self.ty_nesting(inner),
+            TyKind::Ptr(mut_ty) | TyKind::Ref(_, mut_ty) | TyKind::PinnedRef(_, mut_ty) => {
+                self.ty_nesting(&mut_ty.ty)
+            }
+            TyKind::Tup(tys) => deepest(tys.iter().map(|ty| self.ty_nesting(ty))),
+            TyKind::Path(qself, path) => {
+                let qself = qself.iter().map(|qself| self.ty_nesting(&qself.ty));
+                deepest(qself.chain([self.path_nesting(path, ty.span)]))
+            }
+            TyKind::TraitObject(bounds, _) | TyKind::ImplTrait(_, bounds) => {
+                self.bounds_nesting(bounds)
+            }
+            TyKind::FnPtr(fn_ptr) => self.level(ty.span, self.decl_nesting(&fn_ptr.decl)),
+            _ => Nesting::default(),
+        }
+    }
+
+    /// Nesting of a path with generic arguments, which counts as one level unless it names a
+    /// transparent wrapper.
+    fn path_nesting(&self, path: &Path, span: Span) -> Nesting {
+        let mut args = vec![];
+        for segment_args in path
+            .segments
+            .iter()
+            .filter_map(|segment| segment.args.as_deref())
+        {
+            match segment_args {
+                GenericArgs::AngleBracketed(angle_args) => {
+                    for arg in &angle_args.args {
+                        match arg {
+                            AngleBracketedArg::Arg(GenericArg::Type(ty)) => {
+                                args.push(self.ty_nesting(ty));
+                            }
+                            AngleBracketedArg::Constraint(constraint) => match &constraint.kind {
+                                AssocItemConstraintKind::Equality { term: Term::Ty(ty) } => {
+                                    args.push(self.ty_nesting(ty))
+                                }
+                                AssocItemConstraintKind::Bound { bounds } => {
+                                    args.push(self.bounds_nesting(bounds));
+                                }
+                                AssocItemConstraintKind::Equality { .. } => {}
+                            },
+                            AngleBracketedArg::Arg(_) => {}
+                        }
+                    }
+                }
+                GenericArgs::Parenthesized(paren_args) => {
+                    args.extend(paren_args.inputs.iter().map(|ty| self.ty_nesting(ty)));
+                    if let FnRetTy::Ty(ty) = &paren_args.output {
+                        args.push(self.ty_nesting(ty));
+                    }
+                }
+                GenericArgs::ParenthesizedElided(_) => {}
+            }
+        }
+
+        let transparent = path.segments.last().is_some_and(|segment| {
+            let name = segment.ident.name.as_str();
+            self.config
+                .transparent_wrappers
+                .iter()
+                .any(|wrapper| wrapper == name)
+        });
+        if transparent {
+            return deepest(args);
+        }
+        self.level(span, args)
+    }
+
+    fn bounds_nesting(&self, bounds: &[GenericBound]) -> Nesting {
+        deepest(bounds.iter().filter_map(|bound| match bound {
+            GenericBound::Trait(poly) => Some(self.path_nesting(&poly.trait_ref.path, poly.span)),
+            _ => None,
+        }))
+    }
+
+    fn decl_nesting(&self, decl: &FnDecl) -> Vec<Nesting> {
+        let mut nestings: Vec<_> = decl
+            .inputs
+            .iter()
+            .map(|param| self.ty_nesting(&param.ty))
+            .collect();
+        if let FnRetTy::Ty(ty) = &decl.output {
+            nestings.push(self.ty_nesting(ty));
+        }
+        nestings
+    }
+
+    /// One nesting level at `span` around its type arguments, if it has any.
+    fn level(&self, span: Span, args: Vec<Nesting>) -> Nesting {
+        if args.is_empty() {
+            return Nesting::default();
+        }
+        let mut nesting = deepest(args);
+        nesting.path.insert(0, span);
+        nesting
+    }
+
+    /// Reports a type written with more than `max_type_depth` generic levels, labeling the
+    /// largest inner type that would fit within the budget on its own.
+    fn check_type(&self, cx: &EarlyContext<'_>, ty: &Ty) {
+        if ty.span.from_expansion() {
+            return;
+        }
+
+        let nesting = self.ty_nesting(ty);
+        let max = self.config.max_type_depth;
+        let depth = nesting.depth();
+        if depth <= max {
+            return;
+        }
+
+        cx.span_lint(TYPE_NESTING_DEPTH, ty.span, |diag| {
+            diag.primary_message(format!(
+                "type nesting depth: {max} max allowed, {depth} levels found"
+            ));
+            if let Some(span) = nesting.path.get(depth - max) {
+                diag.span_label(*span, NAME_LABEL);
+            }
+            diag.help(HELP_MESSAGE);
+        });
+    }
+
+    /// Checks the type of an associated type in an impl, or its default in a trait.
+    fn check_assoc_type(&self, cx: &EarlyContext<'_>, item: &AssocItem) {
+        if let AssocItemKind::Type(alias) = &item.kind
+            && let Some(ty) = &alias.ty
+        {
+            self.check_type(cx, ty);
+        }
+    }
+
+    fn check_fields(&self, cx: &EarlyContext<'_>, fields: &[FieldDef]) {
+        for field in fields {
+            self.check_type(cx, &field.ty);
+        }
+    }
+}
+
+impl EarlyLintPass for TypeNestingDepth {
+    fn check_item(&mut self, cx: &EarlyContext<'_>, item: &Item) {
+        match &item.kind {
+            ItemKind::Struct(_, _, data) | ItemKind::Union(_, _, data) => {
+                self.check_fields(cx, data.fields());
+            }
+            ItemKind::TyAlias(alias) => {
+                if let Some(ty) = &alias.ty {
+                    self.check_type(cx, ty);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_variant(&mut self, cx: &EarlyContext<'_>, variant: &Variant) {
+        if let VariantData::Struct { fields, .. } | VariantData::Tuple(fields, _) = &variant.data {
+            self.check_fields(cx, fields);
+        }
+    }
+
+    fn check_trait_item(&mut self, cx: &EarlyContext<'_>, item: &AssocItem) {
+        self.check_assoc_type(cx, item);
+    }
+
+    fn check_impl_item(&mut self, cx: &EarlyContext<'_>, item: &AssocItem) {
+        self.check_assoc_type(cx, item);
+    }
+
+    /// Checks fn signatures, except those of trait impl methods, which are dictated by the
+    /// trait.
+    fn check_fn(&mut self, cx: &EarlyContext<'_>, kind: FnKind<'_>, _span: Span, _id: NodeId) {
+        let FnKind::Fn(ctxt, _, func) = kind else {
+            return;
+        };
+        if matches!(ctxt, FnCtxt::Assoc(AssocCtxt::Impl { of_trait: true })) {
+            return;
+        }
+
+        for param in &func.sig.decl.inputs {
+            self.check_type(cx, &param.ty);
+        }
+        if let FnRetTy::Ty(ty) = &func.sig.decl.output {
+            self.check_type(cx, ty);
+        }
+    }
+
+    fn check_local(&mut self, cx: &EarlyContext<'_>, local: &Local) {
+        if let Some(ty) = &local.ty {
+            self.check_type(cx, ty);
+        }
+    }
+}
+
+#[test]
+fn ui() {
+    dylint_uitesting::ui_test(env!("CARGO_PKG_NAME"), "ui");
+}
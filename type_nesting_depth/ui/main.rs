@@ -0,0 +1,85 @@
+#![feature(associated_type_defaults)]
+#![allow(unused, clippy::type_complexity)]
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+struct Registry {
+    //~v ERROR: type nesting depth: 3 max allowed, 5 levels found
+    handlers: Arc<Mutex<HashMap<String, Vec<Option<Result<Box<dyn Fn()>, String>>>>>>,
+    // Within budget: `Arc` and `Rc` are transparent
+    shared: Arc<Mutex<Vec<Rc<Option<u8>>>>>,
+}
+
+enum Message {
+    //~v ERROR: 4 levels
+    Batch(Vec<Vec<Vec<Option<u8>>>>),
+    Named {
+        //~v ERROR: 4 levels
+        groups: HashMap<String, Vec<Vec<Option<u8>>>>,
+    },
+}
+
+//~v ERROR: 4 levels
+type Table = Vec<HashMap<String, Vec<Option<u8>>>>;
+
+type Handler = Option<Result<Box<dyn Fn()>, String>>;
+
+struct Named {
+    // Within budget once the inner part is named
+    handlers: Arc<Mutex<HashMap<String, Vec<Handler>>>>,
+}
+
+//~v ERROR: 4 levels
+fn takes_nested(values: &[Option<Vec<Result<Vec<u8>, ()>>>]) {}
+
+//~v ERROR: 4 levels
+fn returns_nested() -> Option<Vec<Option<Vec<u8>>>> {
+    None
+}
+
+// Tuples, references and arrays do not count as a level
+fn tuples_and_references(values: (&Vec<Option<u8>>, [Option<Vec<u8>>; 2])) {}
+
+// Trait bounds and `Fn` arguments count like generic arguments
+//~v ERROR: 4 levels
+fn returns_iterator() -> impl Iterator<Item = Vec<Option<Vec<u8>>>> {
+    std::iter::empty()
+}
+
+//~v ERROR: 4 levels
+fn takes_callback(callback: Rc<dyn Fn(Vec<Option<Vec<u8>>>)>) {}
+
+fn let_annotation() {
+    //~v ERROR: 4 levels
+    let values: Vec<Option<Vec<Option<u8>>>> = vec![];
+    let shallow: Vec<Option<u8>> = vec![];
+}
+
+trait Visitor {
+    //~v ERROR: 4 levels
+    fn visit(&self, value: Option<Vec<Option<Vec<u8>>>>);
+}
+
+struct Printer;
+
+// Trait impl signatures are dictated by the trait and not reported again
+impl Visitor for Printer {
+    fn visit(&self, value: Option<Vec<Option<Vec<u8>>>>) {}
+}
+
+impl Iterator for Printer {
+    //~v ERROR: 4 levels
+    type Item = Vec<Vec<Vec<Vec<u8>>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        None
+    }
+}
+
+trait Store {
+    //~v ERROR: 4 levels
+    type Entries = Vec<Vec<Vec<Vec<u8>>>>;
+}
+
+fn main() {}
@@ -0,0 +1,53 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Lint configuration
+#[derive(Default, Deserialize)]
+pub struct Config {
+    /// Index of the format string among the arguments of a macro, by macro path or name, for
+    /// macros that take other string arguments that are formatted too
+    #[serde(default)]
+    pub format_string_positions: HashMap<String, usize>,
+
+    /// Macros to lint, by path (`sqlx::query`) or path prefix (`tracing::*`); all macros if empty.
+    /// Paths are matched as written at the call site, so `info!` after `use tracing::info;` is
+    /// matched as `info`, not `tracing::info`
+    #[serde(default)]
+    pub include_macros: Vec<String>,
+
+    /// Macros not to lint, by path or path prefix, even if they are included
+    #[serde(default)]
+    pub exclude_macros: Vec<String>,
+
+    /// Whether to inline `name.as_str()` as `name`, which formats the same if `name` is a
+    /// `String`
+    #[serde(default)]
+    pub inline_as_str: bool,
+
+    /// Minimum supported Rust version, e.g. `1.57`, if the crate has no `#![clippy::msrv]`
+    /// attribute; the `msrv` of `clippy.toml`, else the `rust-version` of the package if unset
+    #[serde(default)]
+    pub msrv: Option<String>,
+}
+
+impl Config {
+    /// Configured index of the format string among the arguments of the macro at `path`,
+    /// looked up by path and then by name.
+    pub fn format_string_position(&self, path: &str) -> Option<usize> {
+        let name = path.rsplit("::").next().unwrap_or(path);
+        self.format_string_positions
+            .get(path)
+            .or_else(|| self.format_string_positions.get(name))
+            .copied()
+    }
+
+    /// Returns `true` if the macro at `path` is included and not excluded.
+    pub fn lints_macro(&self, path: &str) -> bool {
+        let matches = |pattern: &String| match pattern.strip_suffix('*') {
+            Some(prefix) => path.starts_with(prefix),
+            None => pattern == path,
+        };
+        (self.include_macros.is_empty() || self.include_macros.iter().any(matches))
+            && !self.exclude_macros.iter().any(matches)
+    }
+}
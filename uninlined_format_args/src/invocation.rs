@@ -0,0 +1,208 @@
+use crate::rewrite::{SourceArg, Value};
+use rustc_ast::{
+    ExprKind, FormatArgument, FormatArgumentKind,
+    token::{LitKind, Token, TokenKind},
+    tokenstream::{TokenStream, TokenTree},
+};
+use rustc_lint::{EarlyContext, LintContext};
+use rustc_parse::{lexer::StripTokens, new_parser_from_source_str, parser::Parser};
+use rustc_span::{BytePos, FileName, Span};
+
+/// The path and top-level, comma-separated arguments of a macro invocation, re-parsed from its
+/// source
+///
+/// They tell where the format string and its arguments were written, which the spans of the
+/// formatted expressions alone do not, e.g. when a macro binds its arguments in a `match` and
+/// formats the bindings, or when the format string is not directly followed by its arguments.
+pub struct Invocation {
+    callsite: Span,
+    /// Start of the re-parsed source, which corresponds to the start of `callsite`
+    base: BytePos,
+    path: String,
+    args: Vec<Vec<TokenTree>>,
+}
+
+impl Invocation {
+    /// Re-parses the macro invocation at `callsite`.
+    ///
+    /// Returns `None` if it does not parse as a macro call.
+    pub fn parse(cx: &EarlyContext<'_>, callsite: Span) -> Option<Self> {
+        let snippet = cx.sess().source_map().span_to_snippet(callsite).ok()?;
+        let mut parser = new_parser_from_source_str(
+            &cx.sess().psess,
+            FileName::anon_source_code(&snippet),
+            snippet,
+            StripTokens::Nothing,
+        )
+        .map_err(|diags| diags.into_iter().for_each(|diag| diag.cancel()))
+        .ok()?;
+        let expr = parser.parse_expr().map_err(|diag| diag.cancel()).ok()?;
+        let ExprKind::MacCall(mac_call) = &expr.kind else {
+            return None;
+        };
+
+        let mut args = vec![Vec::new()];
+        for tree in mac_call.args.tokens.iter() {
+            match tree {
+                TokenTree::Token(
+                    Token {
+                        kind: TokenKind::Comma,
+                        ..
+                    },
+                    _,
+                ) => args.push(Vec::new()),
+                tree => args.last_mut()?.push(tree.clone()),
+            }
+        }
+        if args.last().is_some_and(Vec::is_empty) {
+            args.pop();
+        }
+
+        let path = mac_call
+            .path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.as_str())
+            .collect::<Vec<_>>()
+            .join("::");
+
+        Some(Self {
+            callsite,
+            base: expr.span.lo(),
+            path,
+            args,
+        })
+    }
+
+    /// Path of the macro as written, e.g. `tracing::info`, without a leading `::`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Maps a span of the re-parsed source to the invocation.
+    fn relocate(&self, span: Span) -> Span {
+        let lo = self.callsite.lo();
+        self.callsite
+            .with_lo(lo + (span.lo() - self.base))
+            .with_hi(lo + (span.hi() - self.base))
+    }
+
+    /// Span of the source of `tokens`, e.g. an argument.
+    fn span_of(&self, tokens: &[TokenTree]) -> Option<Span> {
+        match tokens {
+            [first, .., last] | [first @ last] => {
+                Some(self.relocate(first.span().to(last.span())))
+            }
+            [] => None,
+        }
+    }
+
+    /// Index of the argument written at `span`, e.g. the format string.
+    pub fn position(&self, span: Span) -> Option<usize> {
+        self.args
+            .iter()
+            .position(|arg| self.span_of(arg).is_some_and(|arg| same_source(arg, span)))
+    }
+
+    /// Returns `true` if `arguments`, the explicit arguments of the format string at
+    /// `position`, are written right after it as the remaining arguments of the invocation, in
+    /// order and with the names they have in the format string, wherever their hygiene comes
+    /// from. Otherwise they have to be taken from the invocation with `args_after`.
+    pub fn passes_directly(&self, position: usize, arguments: &[FormatArgument]) -> bool {
+        let Some(written) = self.args.get(position + 1..) else {
+            return false;
+        };
+        written.len() == arguments.len()
+            && written.iter().zip(arguments).all(|(arg, argument)| {
+                let value = match (&argument.kind, arg.as_slice()) {
+                    (
+                        FormatArgumentKind::Named(ident),
+                        [
+                            TokenTree::Token(
+                                Token {
+                                    kind: TokenKind::Ident(name, _),
+                                    ..
+                                },
+                                _,
+                            ),
+                            TokenTree::Token(
+                                Token {
+                                    kind: TokenKind::Eq,
+                                    ..
+                                },
+                                _,
+                            ),
+                            value @ ..,
+                        ],
+                    ) if *name == ident.name => value,
+                    (FormatArgumentKind::Named(_), _) => return false,
+                    (_, value) => value,
+                };
+                self.span_of(value)
+                    .is_some_and(|value| same_source(value, argument.expr.span))
+            })
+    }
+
+    /// Returns `true` if an argument before the one at `position` is another format string, a
+    /// string literal with a placeholder, whose arguments may come between the two.
+    pub fn has_format_string_before(&self, position: usize) -> bool {
+        self.args.iter().take(position).any(|arg| {
+            matches!(
+                arg.as_slice(),
+                [TokenTree::Token(
+                    Token {
+                        kind: TokenKind::Literal(lit),
+                        ..
+                    },
+                    _,
+                )] if matches!(lit.kind, LitKind::Str | LitKind::StrRaw(_))
+                    && lit.symbol.as_str().contains('{')
+            )
+        })
+    }
+
+    /// The arguments that follow the one at `position`, as written. Whatever comes before it,
+    /// like the `target:` and fields of a `tracing` event, is not parsed.
+    ///
+    /// Returns `None` if any of them does not parse as an expression, optionally named.
+    pub fn args_after(&self, cx: &EarlyContext<'_>, position: usize) -> Option<Vec<SourceArg>> {
+        self.args
+            .get(position + 1..)?
+            .iter()
+            .map(|arg| {
+                let value = match arg.as_slice() {
+                    [
+                        TokenTree::Token(
+                            Token {
+                                kind: TokenKind::Ident(..),
+                                ..
+                            },
+                            _,
+                        ),
+                        TokenTree::Token(
+                            Token {
+                                kind: TokenKind::Eq,
+                                ..
+                            },
+                            _,
+                        ),
+                        value @ ..,
+                    ] => value,
+                    value => value,
+                };
+                let mut parser =
+                    Parser::new(&cx.sess().psess, TokenStream::new(value.to_vec()), None);
+                let expr = parser.parse_expr().map_err(|diag| diag.cancel()).ok()?;
+                (parser.token.kind == TokenKind::Eof).then(|| SourceArg {
+                    value: Value::of(&expr),
+                    span: self.relocate(expr.span),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Returns `true` if `a` and `b` cover the same source, whatever their hygiene.
+fn same_source(a: Span, b: Span) -> bool {
+    a.lo() == b.lo() && a.hi() == b.hi()
+}
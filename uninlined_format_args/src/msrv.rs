@@ -0,0 +1,97 @@
+use rustc_ast::Crate;
+use rustc_session::Session;
+use rustc_span::Symbol;
+use std::{env, fs, path::Path};
+
+/// Name of the MSRV in the `#![clippy::msrv]` attribute and in clippy's configuration
+const MSRV_KEY: &str = "msrv";
+
+/// Clippy configuration files, in the order clippy looks for them in a directory
+const CLIPPY_CONFIG_FILES: [&str; 2] = ["clippy.toml", ".clippy.toml"];
+
+/// A Rust version, e.g. `1.58.0`
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RustVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+}
+
+/// First version that captures identifiers in format strings, e.g. `{name}`
+pub const INLINE_CAPTURES: RustVersion = RustVersion {
+    major: 1,
+    minor: 58,
+    patch: 0,
+};
+
+impl RustVersion {
+    /// Parses a version like `1.58` or `1.58.1`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.trim().split('.').map(str::parse::<u32>);
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().transpose().ok()?.unwrap_or(0);
+        let patch = parts.next().transpose().ok()?.unwrap_or(0);
+        parts.next().is_none().then_some(Self {
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+/// Minimum supported Rust version of the linted crate, from its `#![clippy::msrv]` attribute,
+/// else `configured`, else `environment`.
+pub fn of_crate(
+    krate: &Crate,
+    configured: Option<&str>,
+    environment: Option<RustVersion>,
+) -> Option<RustVersion> {
+    let msrv = Symbol::intern(MSRV_KEY);
+    krate
+        .attrs
+        .iter()
+        .find(|attr| attr.path_matches(&[rustc_span::sym::clippy, msrv]))
+        .and_then(|attr| attr.value_str())
+        .map(|value| value.as_str().to_string())
+        .or_else(|| configured.map(str::to_string))
+        .map_or(environment, |text| RustVersion::parse(&text))
+}
+
+/// Minimum supported Rust version from the `msrv` of the clippy configuration of the linted
+/// crate, else from the `rust-version` of its package. The environment variables and the file
+/// read are recorded as dependencies of the crate, so that cargo checks it again when they
+/// change. Dependencies are written before the early lint passes run, so this is called when
+/// the lint is registered.
+pub fn of_environment(sess: &Session) -> Option<RustVersion> {
+    clippy_msrv(sess)
+        .or_else(|| tracked_env_var(sess, "CARGO_PKG_RUST_VERSION"))
+        .and_then(|text| RustVersion::parse(&text))
+}
+
+/// `msrv` of the clippy configuration that clippy would use for the linted crate: the first
+/// `clippy.toml` or `.clippy.toml` in `CLIPPY_CONF_DIR`, else in `CARGO_MANIFEST_DIR`, or in
+/// the nearest of their ancestors.
+fn clippy_msrv(sess: &Session) -> Option<String> {
+    let start = tracked_env_var(sess, "CLIPPY_CONF_DIR")
+        .or_else(|| tracked_env_var(sess, "CARGO_MANIFEST_DIR"))?;
+    let file = Path::new(&start)
+        .ancestors()
+        .flat_map(|dir| CLIPPY_CONFIG_FILES.map(|name| dir.join(name)))
+        .find(|path| path.is_file())?;
+    sess.psess
+        .file_depinfo
+        .lock()
+        .insert(Symbol::intern(&file.to_string_lossy()));
+    let config: toml::Table = fs::read_to_string(file).ok()?.parse().ok()?;
+    config.get(MSRV_KEY)?.as_str().map(str::to_string)
+}
+
+/// Reads an environment variable and records it, with its value, as a dependency of the crate.
+fn tracked_env_var(sess: &Session, name: &str) -> Option<String> {
+    let value = env::var(name).ok();
+    sess.psess.env_depinfo.lock().insert((
+        Symbol::intern(name),
+        value.as_deref().map(Symbol::intern),
+    ));
+    value
+}
@@ -0,0 +1,414 @@
+use rustc_ast::{
+    BorrowKind, Expr, ExprKind, FormatAlignment, FormatArgPosition, FormatArgPositionKind,
+    FormatArgs, FormatArgsPiece, FormatArgument, FormatArgumentKind, FormatCount, FormatDebugHex,
+    FormatOptions, FormatPlaceholder, FormatSign, FormatTrait, LitKind, Mutability, token,
+};
+use rustc_lint::{EarlyContext, LintContext};
+use rustc_span::{Span, Symbol};
+
+/// What an explicit argument of the format string was written as
+pub enum Value {
+    /// A plain identifier, e.g. `name`. A raw identifier like `r#type` holds its name without
+    /// `r#`, which is how a format string refers to it: format strings take keywords as names
+    /// and reject `{r#type}`.
+    Ident(Symbol),
+    /// A plain identifier behind shared references, e.g. `&name` or `&&name`
+    Ref(Symbol),
+    /// A plain identifier viewed as a string slice, e.g. `name.as_str()`
+    AsStr(Symbol),
+    /// A string literal, holding its unescaped contents
+    Str(Symbol),
+    /// Anything else
+    Other,
+}
+
+impl Value {
+    pub fn of(expr: &Expr) -> Self {
+        match &expr.kind {
+            ExprKind::Path(None, path) => match path.segments.as_slice() {
+                [segment] if segment.args.is_none() => Self::Ident(segment.ident.name),
+                _ => Self::Other,
+            },
+            ExprKind::AddrOf(BorrowKind::Ref, Mutability::Not, inner) => match Self::of(inner) {
+                Self::Ident(name) | Self::Ref(name) => Self::Ref(name),
+                _ => Self::Other,
+            },
+            ExprKind::MethodCall(call)
+                if call.seg.ident.name.as_str() == "as_str"
+                    && call.seg.args.is_none()
+                    && call.args.is_empty() =>
+            {
+                match Self::of(&call.receiver) {
+                    Self::Ident(name) => Self::AsStr(name),
+                    _ => Self::Other,
+                }
+            }
+            ExprKind::Lit(lit) => match LitKind::from_token_lit(*lit) {
+                Ok(LitKind::Str(contents, _)) => Self::Str(contents),
+                _ => Self::Other,
+            },
+            _ => Self::Other,
+        }
+    }
+}
+
+/// An explicit argument of the format string as written in the macro invocation
+pub struct SourceArg {
+    pub value: Value,
+    /// Span of the value, without the `name =` of a named argument. Its syntax context tells
+    /// which bindings an identifier in it refers to.
+    pub span: Span,
+}
+
+/// What the placeholders of an inlined argument are replaced with
+enum Inline {
+    /// `{}` becomes `{name}`, keeping the format spec
+    Ident(Symbol),
+    /// `{}` becomes the text of a string literal, escaped for the format string
+    Text(String),
+}
+
+/// Suggested edits that inline the arguments of a format string
+pub struct Rewrite {
+    pub fixes: Vec<(Span, String)>,
+    /// Whether a named argument was inlined, e.g. `name = value`
+    pub named: bool,
+    /// Number of explicit arguments that are kept
+    pub remaining: usize,
+}
+
+/// Plans the inlining of the arguments of `format_args`, written as `args` after the format
+/// string, into the placeholders that use them. Arguments that are plain identifiers are
+/// inlined if `capture_idents` is set, wherever they are used, including as a width or
+/// precision and when the same argument fills several placeholders. References to them are
+/// inlined without the `&`, as are `.as_str()` views if `inline_as_str` is set, unless they are
+/// formatted as pointers. String literals are inlined if they are only displayed as is.
+/// Positional references to the remaining arguments are renumbered, and made explicit where the
+/// implicit order no longer matches.
+///
+/// Returns `None` if no argument can be inlined.
+pub fn plan(
+    cx: &EarlyContext<'_>,
+    format_args: &FormatArgs,
+    args: &[SourceArg],
+    capture_idents: bool,
+    inline_as_str: bool,
+) -> Option<Rewrite> {
+    let explicit = format_args.arguments.explicit_args();
+    if args.len() != explicit.len() {
+        return None;
+    }
+
+    let mut uses = vec![Vec::new(); explicit.len()];
+    for piece in &format_args.template {
+        let FormatArgsPiece::Placeholder(placeholder) = piece else {
+            continue;
+        };
+        placeholder.span?;
+        for (position, is_count) in positions(placeholder) {
+            let index = position.index.ok()?;
+            if let Some(uses) = uses.get_mut(index) {
+                uses.push((is_count, placeholder));
+            }
+        }
+    }
+
+    let mut inlines: Vec<Option<Inline>> = explicit
+        .iter()
+        .zip(args)
+        .zip(&uses)
+        .map(|((argument, arg), uses)| {
+            if uses.is_empty() {
+                return None;
+            }
+            if !captures_same_binding(format_args, arg) {
+                return None;
+            }
+            match (&argument.kind, &arg.value) {
+                (_, Value::Ident(name)) if capture_idents => Some(Inline::Ident(*name)),
+                (_, Value::Ref(name)) if capture_idents && !keeps_reference(uses) => {
+                    Some(Inline::Ident(*name))
+                }
+                (_, Value::AsStr(name))
+                    if capture_idents && inline_as_str && !keeps_reference(uses) =>
+                {
+                    Some(Inline::Ident(*name))
+                }
+                (FormatArgumentKind::Normal, Value::Str(contents))
+                    if uses.iter().all(|&(is_count, placeholder)| {
+                            !is_count
+                                && placeholder.format_trait == FormatTrait::Display
+                                && placeholder.format_options == FormatOptions::default()
+                        }) =>
+                {
+                    escape(contents.as_str(), format_args.uncooked_fmt_str.0).map(Inline::Text)
+                }
+                _ => None,
+            }
+        })
+        .collect();
+
+    keep_taken_names(explicit, &mut inlines);
+    if inlines.iter().all(Option::is_none) {
+        return None;
+    }
+
+    let mut new_indices = Vec::with_capacity(explicit.len());
+    let mut remaining = 0;
+    for inline in &inlines {
+        new_indices.push(remaining);
+        remaining += usize::from(inline.is_none());
+    }
+
+    let source_map = cx.sess().source_map();
+    let mut fixes = Vec::new();
+    let mut next = 0;
+    for piece in &format_args.template {
+        let FormatArgsPiece::Placeholder(placeholder) = piece else {
+            continue;
+        };
+        let span = placeholder.span?;
+        let replacement = if let Some(Inline::Text(text)) = placeholder
+            .argument
+            .index
+            .ok()
+            .and_then(|index| inlines.get(index)?.as_ref())
+        {
+            text.clone()
+        } else {
+            placeholder_text(placeholder, |position| {
+                position_text(format_args, position, &inlines, &new_indices, &mut next)
+            })
+        };
+        if source_map.span_to_snippet(span).ok().as_ref() != Some(&replacement) {
+            fixes.push((span, replacement));
+        }
+    }
+    for (index, inline) in inlines.iter().enumerate() {
+        if inline.is_none() {
+            continue;
+        }
+        let previous = match index.checked_sub(1) {
+            Some(previous) => args[previous].span,
+            None => format_args.span,
+        };
+        fixes.push((args[index].span.with_lo(previous.hi()), String::new()));
+    }
+
+    let named = explicit.iter().zip(&inlines).any(|(argument, inline)| {
+        inline.is_some() && matches!(argument.kind, FormatArgumentKind::Named(_))
+    });
+    Some(Rewrite {
+        fixes,
+        named,
+        remaining,
+    })
+}
+
+/// Returns `true` unless `arg` names a binding that the format string could not capture. A
+/// captured name resolves with the hygiene of the format string, so it may not see a binding
+/// that a macro introduced under that name.
+fn captures_same_binding(format_args: &FormatArgs, arg: &SourceArg) -> bool {
+    !matches!(arg.value, Value::Ident(_) | Value::Ref(_) | Value::AsStr(_))
+        || arg.span.eq_ctxt(format_args.span)
+}
+
+/// Keeps the arguments whose name would be inlined while a named argument that stays takes
+/// that name, which would then be formatted instead. Keeping one may keep another, so this
+/// repeats until no inlined name is taken.
+fn keep_taken_names(explicit: &[FormatArgument], inlines: &mut [Option<Inline>]) {
+    loop {
+        let taken = |name: Symbol, inlines: &[Option<Inline>]| {
+            explicit.iter().zip(inlines).any(|(argument, inline)| {
+                inline.is_none()
+                    && matches!(argument.kind, FormatArgumentKind::Named(ident) if ident.name == name)
+            })
+        };
+        let Some(index) = inlines.iter().position(
+            |inline| matches!(inline, Some(Inline::Ident(name)) if taken(*name, inlines)),
+        ) else {
+            return;
+        };
+        inlines[index] = None;
+    }
+}
+
+/// Returns `true` if a reference with these `uses` cannot be replaced by what it refers to.
+/// `&T` formats like `T` except for its address, so this is only when it is formatted as a
+/// pointer or used as a count, which must be a `usize`.
+fn keeps_reference(uses: &[(bool, &FormatPlaceholder)]) -> bool {
+    uses.iter().any(|&(is_count, placeholder)| {
+        is_count || placeholder.format_trait == FormatTrait::Pointer
+    })
+}
+
+/// The argument positions a placeholder refers to, each with whether it is a width or
+/// precision count rather than the formatted value.
+fn positions(placeholder: &FormatPlaceholder) -> impl Iterator<Item = (&FormatArgPosition, bool)> {
+    let counts = [
+        &placeholder.format_options.width,
+        &placeholder.format_options.precision,
+    ]
+    .into_iter()
+    .filter_map(|count| match count {
+        Some(FormatCount::Argument(position)) => Some((position, true)),
+        _ => None,
+    });
+    std::iter::once((&placeholder.argument, false)).chain(counts)
+}
+
+/// Text of an argument position in the rewritten format string: the name of an inlined
+/// identifier or of a named argument, or the renumbered index of a positional argument. An
+/// implicit position stays implicit, as an empty string, if it is still the `next` implicit
+/// argument of the rewritten format string, which it then advances, and is written as its
+/// index otherwise.
+fn position_text(
+    format_args: &FormatArgs,
+    position: &FormatArgPosition,
+    inlines: &[Option<Inline>],
+    new_indices: &[usize],
+    next: &mut usize,
+) -> String {
+    let index = position.index.unwrap_or_else(|index| index);
+    if let Some(Some(Inline::Ident(name))) = inlines.get(index) {
+        return name.to_string();
+    }
+    let new_index = new_indices.get(index).copied().unwrap_or(index);
+    match position.kind {
+        FormatArgPositionKind::Implicit if new_index == *next => {
+            *next += 1;
+            String::new()
+        }
+        FormatArgPositionKind::Implicit | FormatArgPositionKind::Number => new_index.to_string(),
+        FormatArgPositionKind::Named => format_args
+            .arguments
+            .all_args()
+            .get(index)
+            .and_then(|argument| argument.kind.ident())
+            .map(|ident| ident.name.to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Rewritten text of `placeholder`, with argument positions rendered by `text` in the order
+/// the format string takes them: the width, then the precision, as `{:.*}` takes its
+/// precision before the formatted argument, then the argument.
+fn placeholder_text(
+    placeholder: &FormatPlaceholder,
+    mut text: impl FnMut(&FormatArgPosition) -> String,
+) -> String {
+    let options = &placeholder.format_options;
+    let width = options
+        .width
+        .as_ref()
+        .map(|width| count_text(width, &mut text));
+    let precision = options
+        .precision
+        .as_ref()
+        .map(|precision| count_text(precision, &mut text));
+    let argument = text(&placeholder.argument);
+    render(placeholder, &argument, width, precision)
+}
+
+/// Text of a width or precision in the rewritten format string, e.g. `10`, `width$` or `*`,
+/// with argument positions rendered by `text`.
+fn count_text(count: &FormatCount, text: impl FnOnce(&FormatArgPosition) -> String) -> String {
+    match count {
+        FormatCount::Literal(n) => n.to_string(),
+        FormatCount::Argument(position) => match text(position) {
+            implicit if implicit.is_empty() => "*".to_string(),
+            argument => format!("{argument}$"),
+        },
+    }
+}
+
+/// Renders `{argument:spec}` for a placeholder with its `width` and `precision` already
+/// rendered, e.g. `{name:>width$}`.
+fn render(
+    placeholder: &FormatPlaceholder,
+    argument: &str,
+    width: Option<String>,
+    precision: Option<String>,
+) -> String {
+    let options = &placeholder.format_options;
+
+    let mut spec = String::new();
+    if let Some(alignment) = options.alignment {
+        spec.extend(options.fill);
+        spec.push(match alignment {
+            FormatAlignment::Left => '<',
+            FormatAlignment::Right => '>',
+            FormatAlignment::Center => '^',
+        });
+    }
+    match options.sign {
+        Some(FormatSign::Plus) => spec.push('+'),
+        Some(FormatSign::Minus) => spec.push('-'),
+        None => {}
+    }
+    if options.alternate {
+        spec.push('#');
+    }
+    if options.zero_pad {
+        spec.push('0');
+    }
+    if let Some(width) = width {
+        spec.push_str(&width);
+    }
+    if let Some(precision) = precision {
+        spec.push('.');
+        spec.push_str(&precision);
+    }
+    match options.debug_hex {
+        Some(FormatDebugHex::Lower) => spec.push('x'),
+        Some(FormatDebugHex::Upper) => spec.push('X'),
+        None => {}
+    }
+    spec.push_str(match placeholder.format_trait {
+        FormatTrait::Display => "",
+        FormatTrait::Debug => "?",
+        FormatTrait::LowerExp => "e",
+        FormatTrait::UpperExp => "E",
+        FormatTrait::Octal => "o",
+        FormatTrait::Pointer => "p",
+        FormatTrait::Binary => "b",
+        FormatTrait::LowerHex => "x",
+        FormatTrait::UpperHex => "X",
+    });
+
+    if spec.is_empty() {
+        format!("{{{argument}}}")
+    } else {
+        format!("{{{argument}:{spec}}}")
+    }
+}
+
+/// Escapes `text` to appear literally in a format string written as a literal of kind
+/// `fmt_kind`: braces are doubled, and quotes, backslashes and control characters escaped in
+/// a normal string.
+///
+/// Returns `None` if `text` cannot appear in the raw string.
+fn escape(text: &str, fmt_kind: token::LitKind) -> Option<String> {
+    let braces = text.replace('{', "{{").replace('}', "}}");
+    match fmt_kind {
+        token::LitKind::Str => Some(braces.chars().fold(String::new(), |mut escaped, c| {
+            match c {
+                '"' | '\\' => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if c.is_control() => escaped.extend(c.escape_unicode()),
+                c => escaped.push(c),
+            }
+            escaped
+        })),
+        token::LitKind::StrRaw(hashes) => {
+            let terminator = format!("\"{}", "#".repeat(usize::from(hashes)));
+            (!braces.contains(&terminator)).then_some(braces)
+        }
+        _ => None,
+    }
+}
@@ -1,4 +1,4 @@
-#![allow(unused)]
+#![allow(unused, clippy::useless_vec)]
 
 mod __private {
     pub use std::format;
@@ -67,6 +67,40 @@ macro_rules! mock_format_ident_impl {
     };
 }
 
+macro_rules! ensure {
+    ($cond:expr, $($arg:tt)+) => {
+        if $cond { Ok(()) } else { Err(format!($($arg)+)) }
+    };
+}
+
+macro_rules! expect_that {
+    ($value:expr, $($arg:tt)+) => {
+        if $value.is_empty() {
+            println!($($arg)+);
+        }
+    };
+}
+
+macro_rules! labeled {
+    ($label:literal, $value:expr, $($arg:tt)+) => {
+        print!($label, $value);
+        println!($($arg)+);
+    };
+}
+
+macro_rules! both {
+    ($first:literal, $a:expr, $second:literal, $b:expr) => {
+        print!($first, $a);
+        println!($second, $b);
+    };
+}
+
+macro_rules! sql {
+    ($query:literal, $($arg:expr),*) => {
+        format!($query, $($arg),*)
+    };
+}
+
 #[macro_use]
 mod simple_tracing_like {
     // Test a few cases from tracing with complex patterns.
@@ -93,6 +127,27 @@ mod simple_tracing_like {
             println!($($arg)*)
         );
     }
+
+    // Fields before the message, as in `tracing::event!`.
+    macro_rules! event {
+        (target: $target:expr, $($rest:tt)+) => (event!($($rest)+));
+        (parent: $parent:expr, $($rest:tt)+) => (event!($($rest)+));
+        ($($k:ident).+ = %$v:expr, $($rest:tt)+) => (event!($($rest)+));
+        ($($k:ident).+ = ?$v:expr, $($rest:tt)+) => (event!($($rest)+));
+        ($($k:ident).+ = $v:expr, $($rest:tt)+) => (event!($($rest)+));
+        (%$($k:ident).+, $($rest:tt)+) => (event!($($rest)+));
+        (?$($k:ident).+, $($rest:tt)+) => (event!($($rest)+));
+        ($($k:ident).+, $($rest:tt)+) => (event!($($rest)+));
+        ($($arg:tt)+) => (println!($($arg)+));
+    }
+
+    // Fields before the message of a macro that renames its arguments.
+    macro_rules! mock_event {
+        (target: $target:expr, $($rest:tt)+) => (mock_event!($($rest)+));
+        ($($k:ident).+ = %$v:expr, $($rest:tt)+) => (mock_event!($($rest)+));
+        ($($k:ident).+, $($rest:tt)+) => (mock_event!($($rest)+));
+        ($($arg:tt)+) => (mock_format_ident!($($arg)+));
+    }
 }
 
 fn main() {
@@ -243,6 +298,10 @@ fn main() {
     //~v uninlined_format_args
     mock_format_ident!("{}", r#"raw"string"with"quotes"#);
 
+    // 4b. String literals passed on as written are inlined the same way
+    //~v uninlined_format_args
+    info!("{} {}", "literal,with,commas", var1);
+
     // 5. Complex expressions as arguments
     mock_format_ident!("{}", format!("nested,format,call"));
 
@@ -337,7 +396,6 @@ fn main() {
     info!("hello {value}", value = 42);
     info!("hello {value}", value = format!("nested"));
     info!("hello {value:?}", value = vec![1, 2, 3]);
-    info!("hello {value:?}", value = &val);
     unsafe {
         info!("hello {value}", value = *ptr);
     }
@@ -346,4 +404,153 @@ fn main() {
     info!("hello {value}", value = val.to_string());
     info!("hello {value}", value = val + 1);
     info!("hello {value}", value = (val)); // parentheses make it not a simple ident
+
+    // Format strings that need care when rewritten
+    //~v uninlined_format_args
+    println!(r#"raw "{}" string"#, a);
+    //~v uninlined_format_args
+    println!("escaped \"{}\" quotes", a);
+    //~v uninlined_format_args
+    println!("{{{}}} and {{}} around {:?}", a, b);
+    //~v uninlined_format_args
+    println!("héllo → {} ✓ {:?}", a, b);
+    //~v uninlined_format_args
+    println!("{} {}", a == c, c);
+    //~v uninlined_format_args
+    println!("{x} {}", a == c, x = val);
+    //~v uninlined_format_args
+    println!("{} {1}", a, c);
+    //~v uninlined_format_args
+    mock_format_ident!(r#"{}"#, "quo\"te");
+    //~v uninlined_format_args
+    mock_format_ident!("{}-{}", "br{ace}", "new\nline");
+
+    // SHOULD NOT LINT: `{a}` would refer to the named argument
+    println!("{} {a}", a, a = c + 1);
+
+    // Structured fields before the message
+    let user = "ferris";
+    let count = 3;
+    //~v uninlined_format_args
+    event!(user = %user, count, "processed {} items", c);
+    //~v uninlined_format_args
+    event!(target: "app", ?b, "{} and {:?}", a, b);
+    //~v uninlined_format_args
+    event!(parent: None::<()>, user = ?b, "{x}", x = a);
+    //~v uninlined_format_args
+    event!(note = "{}", user.len = user.len(), "message {}", a);
+    //~v uninlined_format_args
+    mock_event!(target: "app", user = %user, count, "{}/{}", a, "total");
+    event!(user = %user, count, "processed {c} items");
+    event!(user = user, "{}", user.len());
+
+    // Format strings after other arguments
+    use std::fmt::Write as _;
+    let mut out = String::new();
+    //~v uninlined_format_args
+    write!(out, "{}", a).unwrap();
+    //~v uninlined_format_args
+    writeln!(&mut out, "{} {:?}", a, b).unwrap();
+    //~v uninlined_format_args
+    assert_eq!(a, 1, "{} != {}", a, c);
+    //~v uninlined_format_args
+    assert!(a == 1, "unexpected {:?}", b);
+    //~v uninlined_format_args
+    let _ = ensure!(a == 1, "{} is not one", a);
+    //~v uninlined_format_args
+    expect_that!(user, "{} is empty", user);
+    // `labeled` is configured to take its third argument as the format string, so the label,
+    // which is formatted with the second, is left alone.
+    //~v uninlined_format_args
+    labeled!("{}: ", a, "{} again", c);
+    // `both` matches exactly two format strings and their arguments, so inlining either one's
+    // argument would no longer match, and neither is reported.
+    both!("{}: ", a, "{} again", c);
+
+    // `sql` is excluded, so its query is left alone.
+    let _ = sql!("SELECT {}", a);
+
+    // Width and precision arguments
+    let prec = 2;
+    let x = 1.2345;
+    //~v uninlined_format_args
+    println!("{:1$}", a, width);
+    //~v uninlined_format_args
+    println!("{:1$}", a, width + 1);
+    //~v uninlined_format_args
+    println!("{0:>1$} {0}", a, width);
+    //~v uninlined_format_args
+    println!("{:.*}", prec, x);
+    //~v uninlined_format_args
+    println!("{:.*} {}", prec, x + 1.0, a);
+    //~v uninlined_format_args
+    println!("{} {:.*}", a, 2, x);
+
+    // Repeated arguments
+    //~v uninlined_format_args
+    println!("{} and {} again", a, a);
+    //~v uninlined_format_args
+    println!("{0} {0}", x);
+    //~v uninlined_format_args
+    println!("{1:?} {} {}", a + 1, x);
+    //~v uninlined_format_args
+    mock_format_ident!("{0}_{0}", "twice");
+
+    // References format like what they refer to
+    let label = String::from("label");
+    //~v uninlined_format_args
+    println!("{}", &a);
+    //~v uninlined_format_args
+    println!("{:?} {}", &&b, &user);
+    //~v uninlined_format_args
+    info!("hello {value:?}", value = &val);
+    //~v uninlined_format_args
+    println!("{}", label.as_str());
+    // The address of a reference is not the address of what it refers to.
+    println!("{:p}", &a);
+
+    // Raw identifiers are referred to without `r#`
+    //~v uninlined_format_args
+    println!("{kind}", kind = r#type);
+    //~v uninlined_format_args
+    println!("{type}", r#type = a);
+
+    // SHOULD NOT LINT: the format string cannot see a binding introduced by the macro
+    macro_rules! with_local {
+        ($fmt:literal) => {{
+            let local = 1;
+            println!($fmt, local);
+        }};
+    }
+    with_local!("{}");
+
+    // One diagnostic for a macro that formats its arguments twice. rustc would drop a second,
+    // identical one, so the test turns that deduplication off.
+    macro_rules! log_twice {
+        ($($arg:tt)+) => {{
+            let _ = format!($($arg)+);
+            println!($($arg)+);
+        }};
+    }
+    //~v uninlined_format_args
+    log_twice!("{}", a);
+
+    // `dsl::query` is excluded, so its query is left alone.
+    let _ = dsl::query!("SELECT {}", a);
+
+    // `query` is matched as written, not as the `dsl::query` it is imported from, so it is
+    // linted.
+    //~v uninlined_format_args
+    let _ = query!("SELECT {}", a);
 }
+
+mod dsl {
+    macro_rules! query {
+        ($query:literal, $($arg:expr),*) => {
+            format!($query, $($arg),*)
+        };
+    }
+    pub(crate) use query;
+}
+
+use dsl::query;
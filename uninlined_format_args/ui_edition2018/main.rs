@@ -0,0 +1,30 @@
+//@edition:2018
+#![allow(unused)]
+
+fn main() {
+    let a = 1;
+    let b = Some("test");
+    let fail = a == 2;
+
+    // A lone string is not a format string in `panic!` and friends before the 2021 edition.
+    if fail {
+        panic!("{}", a);
+    }
+    if fail {
+        unreachable!("{:?}", b);
+    }
+    assert!(!fail, "{}", a);
+    debug_assert!(!fail, "{} {:?}", a, b);
+
+    // Keeping an argument keeps the string formatted.
+    if fail {
+        //~v uninlined_format_args
+        panic!("{} {}", a, b.is_some());
+    }
+    //~v uninlined_format_args
+    assert!(!fail, "{:?} {}", b, a + 1);
+
+    // Other macros take a lone format string in every edition.
+    //~v uninlined_format_args
+    println!("{}", a);
+}
@@ -0,0 +1,37 @@
+#![feature(custom_inner_attributes)]
+#![clippy::msrv = "1.57"]
+#![allow(unused)]
+
+// Binds its arguments before formatting them, so they are taken from the invocation.
+macro_rules! bound {
+    ($fmt:literal) => {
+        println!($fmt)
+    };
+    ($fmt:literal, $a:expr) => {
+        match $a {
+            a => println!($fmt, a),
+        }
+    };
+    ($fmt:literal, $a:expr, $b:expr) => {
+        match ($a, $b) {
+            (a, b) => println!($fmt, a, b),
+        }
+    };
+}
+
+fn main() {
+    let a = 1;
+    let b = Some("test");
+
+    // Format strings cannot capture variables before Rust 1.58.
+    println!("{}", a);
+    println!("{} {:?}", a, b);
+    println!("{x}", x = a);
+    bound!("{}", a);
+
+    // String literals are still inlined.
+    //~v uninlined_format_args
+    bound!("{}", "literal");
+    //~v uninlined_format_args
+    bound!("{} {}", a, "literal");
+}